@@ -0,0 +1,19 @@
+fn main() {
+    // generates the C header of the ffi module, the copy in ffi/ is checked by a test in ffi.rs
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let directory = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", directory)).unwrap();
+        cbindgen::Builder::new()
+            .with_src(format!("{}/src/ffi.rs", directory))
+            .with_config(config)
+            .generate()
+            .expect("cannot generate the C header")
+            .write_to_file(format!(
+                "{}/marjapussi.h",
+                std::env::var("OUT_DIR").unwrap()
+            ));
+    }
+}
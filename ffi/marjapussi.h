@@ -0,0 +1,125 @@
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#ifndef MARJAPUSSI_H
+#define MARJAPUSSI_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MP_OK 0
+
+#define MP_ERR_NULL -1
+
+#define MP_ERR_INDEX -2
+
+#define MP_ERR_ILLEGAL -3
+
+/**
+ * Snapshot of everything instead of a seat's view.
+ */
+#define MP_ALL_SEATS -1
+
+/**
+ * Opaque game handle.
+ */
+typedef struct MpGame MpGame;
+
+/**
+ * Creates a game dealt from the seed, free it with `mp_game_free`.
+ * `name` and `players` (four names) may be null for defaults.
+ * Returns null if a name is no valid UTF-8.
+ *
+ * # Safety
+ * `name` is null or a zero terminated string, `players` is null or points to four of them.
+ */
+MpGame *mp_game_new(const char *name, const char *const *players, uint64_t seed);
+
+/**
+ * # Safety
+ * `game` is null or was returned by this library and is not used afterwards.
+ */
+void mp_game_free(MpGame *game);
+
+/**
+ * A copy of the game which has to be freed separately.
+ *
+ * # Safety
+ * `game` is null or a live handle.
+ */
+MpGame *mp_game_clone(const MpGame *game);
+
+/**
+ * Number of legal actions, 0 once the game ended.
+ *
+ * # Safety
+ * `game` is null or a live handle.
+ */
+size_t mp_game_legal_count(const MpGame *game);
+
+/**
+ * Writes the seat of every legal action into `seats` and the index of the action
+ * for `ai::encoding::action_index` into `indices`, either may be null.
+ * Returns the number of legal actions, at most `capacity` entries are written.
+ *
+ * # Safety
+ * `game` is null or a live handle, the buffers are null or hold `capacity` entries.
+ */
+size_t mp_game_legal_actions(const MpGame *game,
+                             uint8_t *seats,
+                             uint32_t *indices,
+                             size_t capacity);
+
+/**
+ * Writes the legal action at `index` in notation, e.g. `2 bid 140`.
+ *
+ * # Safety
+ * `game` is null or a live handle, `buffer` is null or holds `capacity` bytes.
+ */
+size_t mp_game_legal_action_notation(const MpGame *game,
+                                     size_t index,
+                                     char *buffer,
+                                     size_t capacity);
+
+/**
+ * Applies the legal action at `index` of the enumeration in place.
+ *
+ * # Safety
+ * `game` is null or a live handle.
+ */
+int32_t mp_game_apply_index(MpGame *game, size_t index);
+
+/**
+ * Applies a `;` separated move list in place, all or nothing.
+ *
+ * # Safety
+ * `game` is null or a live handle, `moves` is null or a zero terminated string.
+ */
+int32_t mp_game_apply_notation(MpGame *game, const char *moves);
+
+/**
+ * # Safety
+ * `game` is null or a live handle.
+ */
+bool mp_game_ended(const MpGame *game);
+
+/**
+ * Seat at turn, -1 for null.
+ *
+ * # Safety
+ * `game` is null or a live handle.
+ */
+int32_t mp_game_player_at_turn(const MpGame *game);
+
+/**
+ * Writes the game as JSON: a seat's view for seats 0 to 3,
+ * or with `MP_ALL_SEATS` all hands and events. Returns 0 for null or an invalid seat.
+ *
+ * # Safety
+ * `game` is null or a live handle, `buffer` is null or holds `capacity` bytes.
+ */
+size_t mp_game_snapshot_json(const MpGame *game, int32_t seat, char *buffer, size_t capacity);
+
+#endif  /* MARJAPUSSI_H */
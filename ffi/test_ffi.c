@@ -0,0 +1,48 @@
+/* Plays a game through the C API, see the README for how to build it. */
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "marjapussi.h"
+
+int main(void) {
+    const char *players[4] = {"Ada", "Bo", "Cy", "Di"};
+    MpGame *game = mp_game_new("C test", players, 7);
+    assert(game != NULL);
+    assert(mp_game_legal_count(game) == 4);
+    assert(mp_game_apply_notation(game, "start; start; start; start") == MP_OK);
+    assert(mp_game_apply_notation(game, "bid 500") == MP_ERR_ILLEGAL);
+    assert(mp_game_apply_index(game, 1000) == MP_ERR_INDEX);
+    assert(mp_game_player_at_turn(game) == 0);
+
+    size_t count = mp_game_legal_count(game);
+    uint8_t *seats = malloc(count);
+    uint32_t *indices = malloc(count * sizeof(uint32_t));
+    assert(mp_game_legal_actions(game, seats, indices, count) == count);
+    for (size_t i = 0; i < count; i++) {
+        assert(seats[i] == 0);
+    }
+    free(seats);
+    free(indices);
+
+    char action[32];
+    mp_game_legal_action_notation(game, 1, action, sizeof action);
+    assert(strcmp(action, "0 bid 120") == 0);
+
+    size_t moves = 0;
+    while (!mp_game_ended(game)) {
+        assert(mp_game_apply_index(game, mp_game_legal_count(game) - 1) == MP_OK);
+        moves++;
+    }
+
+    size_t len = mp_game_snapshot_json(game, MP_ALL_SEATS, NULL, 0);
+    char *json = malloc(len + 1);
+    assert(mp_game_snapshot_json(game, MP_ALL_SEATS, json, len + 1) == len);
+    assert(strlen(json) == len && json[0] == '{');
+    free(json);
+
+    mp_game_free(game);
+    printf("played %zu moves, snapshot of %zu bytes\n", moves, len);
+    return 0;
+}
@@ -0,0 +1,9 @@
+pub mod agent;
+pub mod analysis;
+pub mod batch;
+pub mod encoding;
+pub mod export;
+pub mod hand_eval;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+pub mod pass_advisor;
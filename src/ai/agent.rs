@@ -0,0 +1,293 @@
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game::cards::{halves, high_card, pairs, Card, Value};
+use crate::game::gameevent::{ActionType, GameAction, QuestionType};
+use crate::game::gamestate::GamePhase;
+use crate::game::player::{PlaceAtTable, PlayerTrumpPossibilities};
+use crate::game::points::{points_card, points_pair};
+use crate::game::Game;
+
+/**
+ * Something that plays Marjapussi for one seat.
+ * Agents get the full game but must only use what their seat can see,
+ * `legal` contains only actions of that seat and is never empty.
+ */
+pub trait Agent: Send {
+    fn select_action(&mut self, game: &Game, legal: &[GameAction]) -> GameAction;
+}
+
+/// Seat which has to act next and its legal actions, undo requests are left out.
+pub fn next_decision(game: &Game) -> Option<(PlaceAtTable, Vec<GameAction>)> {
+    let legal: Vec<GameAction> = game
+        .legal
+        .iter()
+        .filter(|a| a.action_type != ActionType::UndoRequest)
+        .collect();
+    let player = legal.first()?.player.clone();
+    let legal = legal.into_iter().filter(|a| a.player == player).collect();
+    Some((player, legal))
+}
+
+/// Lets the agents of every seat play until the game ended.
+pub fn play_to_end(mut game: Game, agents: &mut [Box<dyn Agent>; 4]) -> Game {
+    while let Some((player, legal)) = next_decision(&game) {
+        let action = agents[player.0 as usize].select_action(&game, &legal);
+        game = game
+            .apply_action(action)
+            .expect("agent chose an illegal action");
+    }
+    game
+}
+
+/// Picks uniformly among all legal actions.
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> Self {
+        RandomAgent {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn select_action(&mut self, _game: &Game, legal: &[GameAction]) -> GameAction {
+        legal.choose(&mut self.rng).unwrap().clone()
+    }
+}
+
+/**
+ * Simple rule based player, good enough as opponent and for rollouts.
+ * With noise it plays a random legal action with that probability.
+ */
+pub struct HeuristicAgent {
+    rng: StdRng,
+    noise: f64,
+}
+
+impl HeuristicAgent {
+    pub fn new(seed: u64) -> Self {
+        HeuristicAgent::with_noise(seed, 0.0)
+    }
+
+    pub fn with_noise(seed: u64, noise: f64) -> Self {
+        HeuristicAgent {
+            rng: StdRng::seed_from_u64(seed),
+            noise,
+        }
+    }
+}
+
+/// Heuristic agents for all seats, seat `i` is seeded with `seed + i`.
+pub fn heuristic_agents(seed: u64) -> [Box<dyn Agent>; 4] {
+    heuristic_agents_with_noise(seed, 0.0)
+}
+
+pub fn heuristic_agents_with_noise(seed: u64, noise: f64) -> [Box<dyn Agent>; 4] {
+    [0, 1, 2, 3].map(|i| {
+        Box::new(HeuristicAgent::with_noise(seed.wrapping_add(i), noise)) as Box<dyn Agent>
+    })
+}
+
+/// Random agents for all seats, seat `i` is seeded with `seed + i`.
+pub fn random_agents(seed: u64) -> [Box<dyn Agent>; 4] {
+    [0, 1, 2, 3].map(|i| Box::new(RandomAgent::new(seed.wrapping_add(i))) as Box<dyn Agent>)
+}
+
+impl Agent for HeuristicAgent {
+    fn select_action(&mut self, game: &Game, legal: &[GameAction]) -> GameAction {
+        if self.noise > 0.0 && self.rng.random_bool(self.noise) {
+            return legal.choose(&mut self.rng).unwrap().clone();
+        }
+        let player = legal[0].player.clone();
+        let cards = &game.state.player_at_place(player.clone()).cards;
+        let chosen = match game.state.phase {
+            GamePhase::Bidding => heuristic_bid(game, cards),
+            GamePhase::PassingForth => Some(ActionType::Pass(best_cards(cards, pass_forth_score))),
+            GamePhase::PassingBack => Some(ActionType::Pass(best_cards(cards, pass_back_score))),
+            GamePhase::Raising | GamePhase::StartTrick | GamePhase::Trick => {
+                heuristic_question(game, legal).or_else(|| heuristic_card(game, legal))
+            }
+            _ => None,
+        };
+        chosen
+            .map(|action_type| GameAction {
+                action_type,
+                player,
+            })
+            .filter(|action| legal.contains(action))
+            .unwrap_or_else(|| legal[0].clone())
+    }
+}
+
+/// Quick estimate of what a hand can play, without looking at anyone else.
+pub fn estimate_max_bid(cards: &[Card]) -> i32 {
+    let aces = cards.iter().filter(|c| c.value == Value::Ace).count() as i32;
+    let tens = cards.iter().filter(|c| c.value == Value::Ten).count() as i32;
+    let own_pairs = pairs(cards.to_vec());
+    let pair_points: i32 = own_pairs.iter().map(|s| points_pair(*s).0).sum();
+    let single_halves = halves(cards.to_vec()).len() as i32 - own_pairs.len() as i32;
+    let estimate = 100 + 10 * aces + 5 * tens + pair_points + 10 * single_halves;
+    estimate / 5 * 5
+}
+
+fn heuristic_bid(game: &Game, cards: &[Card]) -> Option<ActionType> {
+    let next = game.state.value.0 + 5;
+    if next <= estimate_max_bid(cards) {
+        Some(ActionType::NewBid(next))
+    } else {
+        Some(ActionType::StopBidding)
+    }
+}
+
+/// The four cards with the highest score, sorted as legal passes are.
+fn best_cards(cards: &[Card], score: fn(&Card, &[Card]) -> i32) -> Vec<Card> {
+    let mut scored: Vec<&Card> = cards.iter().collect();
+    scored.sort_by_key(|c| (-score(c, cards), (*c).clone()));
+    let mut chosen: Vec<Card> = scored.into_iter().take(4).cloned().collect();
+    chosen.sort();
+    chosen.reverse();
+    chosen
+}
+
+pub(crate) fn is_half(card: &Card) -> bool {
+    card.value == Value::Ober || card.value == Value::King
+}
+
+/// Partner gives aces, tens and halves to the playing player.
+fn pass_forth_score(card: &Card, _cards: &[Card]) -> i32 {
+    match card.value {
+        Value::Ace => 30,
+        Value::Ten => 15,
+        _ if is_half(card) => 20,
+        _ => points_card(card.clone()).0,
+    }
+}
+
+/// The playing player gives back low cards and keeps pairs and aces.
+fn pass_back_score(card: &Card, cards: &[Card]) -> i32 {
+    if pairs(cards.to_vec()).contains(&card.suit) && is_half(card) {
+        return -100;
+    }
+    match card.value {
+        Value::Ace => -50,
+        Value::Ten => -20,
+        _ if is_half(card) => -15,
+        _ => -(card.value as i32),
+    }
+}
+
+fn heuristic_question(game: &Game, legal: &[GameAction]) -> Option<ActionType> {
+    let announce = legal
+        .iter()
+        .filter_map(|a| match a.action_type {
+            ActionType::AnnounceTrump(suit) => Some(suit),
+            _ => None,
+        })
+        .max_by_key(|suit| points_pair(*suit));
+    if let Some(suit) = announce {
+        return Some(ActionType::AnnounceTrump(suit));
+    }
+    let asking = game.state.player_at_place(legal[0].player.clone());
+    let can_ask = legal
+        .iter()
+        .any(|a| a.action_type == ActionType::Question(QuestionType::Yours));
+    if can_ask && asking.trump == PlayerTrumpPossibilities::Own && game.state.trump.is_none() {
+        return Some(ActionType::Question(QuestionType::Yours));
+    }
+    None
+}
+
+fn heuristic_card(game: &Game, legal: &[GameAction]) -> Option<ActionType> {
+    let cards: Vec<&Card> = legal
+        .iter()
+        .filter_map(|a| match &a.action_type {
+            ActionType::CardPlayed(card) => Some(card),
+            _ => None,
+        })
+        .collect();
+    if cards.is_empty() {
+        return None;
+    }
+    let trump = game.state.trump;
+    let trick: Vec<&Card> = match game.state.current_trick.len() {
+        4 => vec![],
+        _ => game.state.current_trick.iter().collect(),
+    };
+    let points = |c: &&Card| points_card((*c).clone()).0;
+    let chosen = if trick.is_empty() {
+        cards
+            .iter()
+            .max_by_key(|c| (c.value == Value::Ace, Some(c.suit) != trump, c.value))
+    } else {
+        let current_high = high_card(trick.clone(), trump).unwrap();
+        let partner_wins = trick.len() >= 2 && trick[trick.len() - 2] == current_high;
+        let winning: Vec<&&Card> = cards
+            .iter()
+            .filter(|c| {
+                let mut with_card = trick.clone();
+                with_card.push(c);
+                high_card(with_card, trump) == Some(c)
+            })
+            .collect();
+        if partner_wins {
+            cards.iter().max_by_key(|c| (points(c), c.value))
+        } else if !winning.is_empty() {
+            winning.into_iter().min_by_key(|c| (c.value, points(c)))
+        } else {
+            cards.iter().min_by_key(|c| (points(c), c.value))
+        }
+    };
+    chosen.map(|card| ActionType::CardPlayed((*card).clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::gameinfo::GameFinishedInfo;
+    use crate::game::points::Points;
+
+    #[test]
+    fn test_agents_finish_games() {
+        for seed in 0..20 {
+            let names = ["A", "B", "C", "D"].map(String::from);
+            let game = Game::new_seeded(String::from("Agents"), names, seed);
+            let mut agents: [Box<dyn Agent>; 4] = [
+                Box::new(HeuristicAgent::new(seed)),
+                Box::new(RandomAgent::new(seed)),
+                Box::new(HeuristicAgent::with_noise(seed, 0.2)),
+                Box::new(RandomAgent::new(seed + 1)),
+            ];
+            let game = play_to_end(game, &mut agents);
+            assert!(game.ended());
+            let finished = GameFinishedInfo::from(game);
+            let trick_points = finished
+                .tricks
+                .iter()
+                .fold(Points(0), |acc, trick| acc + trick.points);
+            assert_eq!(trick_points, Points(140));
+        }
+    }
+
+    #[test]
+    fn test_estimate_max_bid() {
+        let weak: Vec<Card> = [
+            "g-6", "g-7", "e-6", "e-7", "s-6", "s-7", "r-6", "r-7", "g-8",
+        ]
+        .iter()
+        .map(|c| c.parse().unwrap())
+        .collect();
+        let strong: Vec<Card> = [
+            "r-O", "r-K", "s-O", "s-K", "g-A", "e-A", "s-A", "r-A", "r-Z",
+        ]
+        .iter()
+        .map(|c| c.parse().unwrap())
+        .collect();
+        assert!(estimate_max_bid(&weak) < 120);
+        assert!(estimate_max_bid(&strong) > 250);
+    }
+}
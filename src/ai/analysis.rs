@@ -0,0 +1,286 @@
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use crate::ai::agent::{
+    heuristic_agents_with_noise, next_decision, play_to_end, Agent, HeuristicAgent,
+};
+use crate::game::errors::GameError;
+use crate::game::gameevent::{effective_events, ActionType, GameAction};
+use crate::game::gameinfo::GameFinishedInfo;
+use crate::game::gamestate::GamePhase;
+use crate::game::player::PlaceAtTable;
+use crate::game::Game;
+
+#[derive(Debug, Clone)]
+pub struct AnalysisConfig {
+    /// Games played to the end for every evaluated action
+    pub rollouts: usize,
+    /// Upper bound of evaluated actions per decision, including the played one
+    pub max_alternatives: usize,
+    /// Smaller differences are not reported
+    pub min_points_lost: f64,
+    /// Chance of random moves by the rollout agents
+    pub noise: f64,
+    pub seed: u64,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        AnalysisConfig {
+            rollouts: 4,
+            max_alternatives: 8,
+            min_points_lost: 5.0,
+            noise: 0.1,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MistakeCategory {
+    BidTooHigh,
+    BidTooLow,
+    StoppedTooEarly,
+    Raising,
+    Passing,
+    Question,
+    Announcement,
+    CardPlay,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Mistake {
+    /// Index into the effective events of the game
+    pub move_index: usize,
+    pub player: PlaceAtTable,
+    pub player_name: String,
+    pub phase: String,
+    pub played: GameAction,
+    pub better: GameAction,
+    pub points_lost: f64,
+    pub category: MistakeCategory,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisReport {
+    pub game_name: String,
+    pub players: [String; 4],
+    pub decisions: usize,
+    pub mistakes: Vec<Mistake>,
+    pub points_lost: [f64; 4],
+}
+
+impl AnalysisReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("report is always serializable")
+    }
+}
+
+impl fmt::Display for AnalysisReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Analysis of {}: {} decisions, {} mistakes",
+            self.game_name,
+            self.decisions,
+            self.mistakes.len()
+        )?;
+        for (name, lost) in self.players.iter().zip(self.points_lost) {
+            writeln!(f, "  {:<20} {:>7.1} points lost", name, lost)?;
+        }
+        for mistake in &self.mistakes {
+            writeln!(
+                f,
+                "{:>3}. {} ({}): played {}, better {}, -{:.1} [{:?}]",
+                mistake.move_index + 1,
+                mistake.player_name,
+                mistake.phase,
+                mistake.played.action_type,
+                mistake.better.action_type,
+                mistake.points_lost,
+                mistake.category
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Expected score for the party of the acting player after the action, from rollouts.
+fn evaluate(game: &Game, action: &GameAction, config: &AnalysisConfig, seed: u64) -> f64 {
+    let party = action.player.0 % 2;
+    let next = game
+        .apply_action(action.clone())
+        .expect("only legal actions are evaluated");
+    let total: f64 = (0..config.rollouts.max(1))
+        .map(|rollout| {
+            let rollout_seed = seed.wrapping_add(rollout as u64 * 4);
+            let mut agents = heuristic_agents_with_noise(rollout_seed, config.noise);
+            let finished = GameFinishedInfo::from(play_to_end(next.clone(), &mut agents));
+            finished.party_score(party)
+        })
+        .sum();
+    total / config.rollouts.max(1) as f64
+}
+
+/// The played action, what the heuristic would play and a sample of the rest.
+fn alternatives(
+    game: &Game,
+    played: &GameAction,
+    legal: &[GameAction],
+    config: &AnalysisConfig,
+    rng: &mut StdRng,
+) -> Vec<GameAction> {
+    let mut candidates = vec![played.clone()];
+    let suggested = HeuristicAgent::new(0).select_action(game, legal);
+    if suggested != *played {
+        candidates.push(suggested);
+    }
+    let mut rest: Vec<GameAction> = legal
+        .iter()
+        .filter(|a| !candidates.contains(a))
+        .cloned()
+        .collect();
+    if game.state.phase == GamePhase::Bidding {
+        // only bids close to the current value are realistic
+        rest.sort_by_key(|a| match a.action_type {
+            ActionType::NewBid(value) => value,
+            _ => 0,
+        });
+    } else {
+        rest.shuffle(rng);
+    }
+    candidates.extend(rest);
+    candidates.truncate(config.max_alternatives.max(1));
+    candidates
+}
+
+fn category(phase: &GamePhase, played: &ActionType, better: &ActionType) -> MistakeCategory {
+    match (phase, played, better) {
+        (GamePhase::Bidding, ActionType::StopBidding, _) => MistakeCategory::StoppedTooEarly,
+        (GamePhase::Bidding, ActionType::NewBid(p), ActionType::NewBid(b)) if b > p => {
+            MistakeCategory::BidTooLow
+        }
+        (GamePhase::Bidding, _, _) => MistakeCategory::BidTooHigh,
+        (GamePhase::Raising, _, _) => MistakeCategory::Raising,
+        (GamePhase::PassingForth | GamePhase::PassingBack, _, _) => MistakeCategory::Passing,
+        (_, ActionType::AnnounceTrump(_), _) | (_, _, ActionType::AnnounceTrump(_)) => {
+            MistakeCategory::Announcement
+        }
+        (_, ActionType::Question(_), _) | (_, _, ActionType::Question(_)) => {
+            MistakeCategory::Question
+        }
+        _ => MistakeCategory::CardPlay,
+    }
+}
+
+/**
+ * Replays the effective history and compares every decision with alternatives,
+ * evaluated by heuristic rollouts with all cards known.
+ * Decisions with only one legal action are skipped.
+ */
+pub fn analyse_game(
+    finished: &GameFinishedInfo,
+    config: &AnalysisConfig,
+) -> Result<AnalysisReport, GameError> {
+    let players = finished.info.player_names.clone();
+    let mut game = Game::new(
+        finished.info.name.clone(),
+        players.clone(),
+        Some(finished.info.player_start_cards.clone()),
+    );
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut report = AnalysisReport {
+        game_name: finished.info.name.clone(),
+        players: players.clone(),
+        decisions: 0,
+        mistakes: vec![],
+        points_lost: [0.0; 4],
+    };
+
+    for (move_index, event) in effective_events(&finished.all_events).iter().enumerate() {
+        let played = event.last_action.clone();
+        let legal = next_decision(&game)
+            .map(|(_, legal)| legal)
+            .filter(|legal| legal.contains(&played))
+            .unwrap_or_default();
+        if legal.len() > 1 && game.state.phase != GamePhase::WaitingForStart {
+            report.decisions += 1;
+            let seed = config.seed.wrapping_add(move_index as u64 * 1000);
+            let evaluated: Vec<(GameAction, f64)> =
+                alternatives(&game, &played, &legal, config, &mut rng)
+                    .into_iter()
+                    .map(|action| {
+                        let score = evaluate(&game, &action, config, seed);
+                        (action, score)
+                    })
+                    .collect();
+            let played_score = evaluated[0].1;
+            let (better, best_score) = evaluated
+                .iter()
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap()
+                .clone();
+            let points_lost = best_score - played_score;
+            if points_lost >= config.min_points_lost {
+                let seat = played.player.0 as usize;
+                report.points_lost[seat] += points_lost;
+                report.mistakes.push(Mistake {
+                    move_index,
+                    player: played.player.clone(),
+                    player_name: players[seat].clone(),
+                    phase: format!("{:?}", game.state.phase),
+                    category: category(&game.state.phase, &played.action_type, &better.action_type),
+                    played: played.clone(),
+                    better,
+                    points_lost,
+                });
+            }
+        }
+        game = game.apply_action(played)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{heuristic_agents, random_agents};
+
+    #[test]
+    fn test_analyse_random_game() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let game = Game::new_seeded(String::from("Analysis"), names, 5);
+        let mut agents = random_agents(0);
+        let finished = GameFinishedInfo::from(play_to_end(game, &mut agents));
+        let config = AnalysisConfig {
+            rollouts: 1,
+            max_alternatives: 3,
+            ..AnalysisConfig::default()
+        };
+        let report = analyse_game(&finished, &config).unwrap();
+        assert!(report.decisions > 0);
+        for mistake in &report.mistakes {
+            assert!(mistake.points_lost >= config.min_points_lost);
+            assert_ne!(mistake.played, mistake.better);
+        }
+        let total: f64 = report.mistakes.iter().map(|m| m.points_lost).sum();
+        assert!((total - report.points_lost.iter().sum::<f64>()).abs() < 1e-9);
+        assert!(report.to_json().contains("\"mistakes\""));
+        assert!(report.to_string().starts_with("Analysis of Analysis"));
+    }
+
+    #[test]
+    fn test_party_score_is_zero_sum() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        for seed in 0..5 {
+            let game = Game::new_seeded(String::from("Score"), names.clone(), seed);
+            let mut agents = heuristic_agents(seed);
+            let finished = GameFinishedInfo::from(play_to_end(game, &mut agents));
+            assert_eq!(finished.party_score(0), -finished.party_score(1));
+        }
+    }
+}
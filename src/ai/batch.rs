@@ -0,0 +1,239 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::ai::agent::{play_to_end, Agent};
+use crate::game::gameinfo::GameFinishedInfo;
+use crate::game::player::PlaceAtTable;
+use crate::game::Game;
+use crate::stats::Statistics;
+
+/// Creates the agent of a seat for one game, every game gets fresh agents.
+pub trait AgentFactory: Send + Sync {
+    fn create(&self, seat: PlaceAtTable, seed: u64) -> Box<dyn Agent>;
+}
+
+impl<F> AgentFactory for F
+where
+    F: Fn(PlaceAtTable, u64) -> Box<dyn Agent> + Send + Sync,
+{
+    fn create(&self, seat: PlaceAtTable, seed: u64) -> Box<dyn Agent> {
+        self(seat, seed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub games: usize,
+    /// Game `i` is dealt and played with seeds derived from `seed + i`
+    pub seed: u64,
+    /// Worker threads, at least one
+    pub threads: usize,
+    pub player_names: [String; 4],
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfig {
+            games: 100,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            player_names: ["Seat 0", "Seat 1", "Seat 2", "Seat 3"].map(String::from),
+        }
+    }
+}
+
+/// One finished game of a batch, `index` is its position in the batch.
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    pub index: usize,
+    pub seed: u64,
+    pub game: GameFinishedInfo,
+}
+
+/// Plays game `index` of the batch, only depends on the config and the factory.
+pub fn play_batch_game(
+    config: &BatchConfig,
+    factory: &dyn AgentFactory,
+    index: usize,
+) -> BatchResult {
+    let seed = config.seed.wrapping_add(index as u64);
+    let game = Game::new_seeded(
+        format!("Batch {}", index),
+        config.player_names.clone(),
+        seed,
+    );
+    let mut agents: [Box<dyn Agent>; 4] = [0, 1, 2, 3].map(|seat| {
+        factory.create(
+            PlaceAtTable(seat),
+            seed.wrapping_mul(4).wrapping_add(seat as u64),
+        )
+    });
+    BatchResult {
+        index,
+        seed,
+        game: GameFinishedInfo::from(play_to_end(game, &mut agents)),
+    }
+}
+
+/// Games of a running batch, iterating ends when all workers are done.
+pub struct BatchRun {
+    results: Receiver<BatchResult>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Iterator for BatchRun {
+    type Item = BatchResult;
+
+    fn next(&mut self) -> Option<BatchResult> {
+        self.results.recv().ok()
+    }
+}
+
+impl BatchRun {
+    /// Waits for the workers and resumes the panic of a worker that panicked, whose games are missing.
+    pub fn join(self) {
+        drop(self.results);
+        for worker in self.workers {
+            if let Err(panic) = worker.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+    }
+}
+
+/**
+ * Plays the batch on worker threads and streams every game as soon as it is finished.
+ * Results arrive in any order but each game is the same for any number of threads.
+ * Workers stop early when the run is dropped.
+ */
+pub fn run_batch(config: BatchConfig, factory: Arc<dyn AgentFactory>) -> BatchRun {
+    let (sender, results) = channel();
+    let next = Arc::new(AtomicUsize::new(0));
+    let config = Arc::new(config);
+    let workers = (0..config.threads.max(1))
+        .map(|_| {
+            let (sender, next, config, factory) = (
+                sender.clone(),
+                next.clone(),
+                config.clone(),
+                factory.clone(),
+            );
+            thread::spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= config.games {
+                    break;
+                }
+                if sender
+                    .send(play_batch_game(&config, factory.as_ref(), index))
+                    .is_err()
+                {
+                    break;
+                }
+            })
+        })
+        .collect();
+    BatchRun { results, workers }
+}
+
+/// All games of the batch in batch order, panics if a worker panicked.
+pub fn collect_batch(mut run: BatchRun) -> Vec<BatchResult> {
+    let mut results: Vec<BatchResult> = run.by_ref().collect();
+    run.join();
+    results.sort_by_key(|result| result.index);
+    results
+}
+
+/// Statistics over the batch, games are added in batch order so ratings are reproducible.
+pub fn batch_statistics(config: BatchConfig, factory: Arc<dyn AgentFactory>) -> Statistics {
+    let results = collect_batch(run_batch(config, factory));
+    Statistics::from_games(results.iter().map(|result| &result.game))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{HeuristicAgent, RandomAgent};
+    use crate::game::gameevent::GameAction;
+    use std::panic::AssertUnwindSafe;
+
+    fn helper_factory() -> Arc<dyn AgentFactory> {
+        Arc::new(|seat: PlaceAtTable, seed: u64| -> Box<dyn Agent> {
+            match seat.0 % 2 {
+                0 => Box::new(HeuristicAgent::with_noise(seed, 0.1)),
+                _ => Box::new(RandomAgent::new(seed)),
+            }
+        })
+    }
+
+    fn helper_actions(result: &BatchResult) -> Vec<GameAction> {
+        result
+            .game
+            .all_events
+            .iter()
+            .map(|event| event.last_action.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_is_independent_of_threads() {
+        let config = BatchConfig {
+            games: 12,
+            seed: 42,
+            threads: 1,
+            ..BatchConfig::default()
+        };
+        let single = collect_batch(run_batch(config.clone(), helper_factory()));
+        let parallel = collect_batch(run_batch(
+            BatchConfig {
+                threads: 4,
+                ..config.clone()
+            },
+            helper_factory(),
+        ));
+        assert_eq!(single.len(), 12);
+        assert_eq!(parallel.len(), 12);
+        for (a, b) in single.iter().zip(&parallel) {
+            assert_eq!(a.index, b.index);
+            assert_eq!(
+                a.game.info.player_start_cards,
+                b.game.info.player_start_cards
+            );
+            assert_eq!(helper_actions(a), helper_actions(b));
+            assert_eq!(a.game.tricks, b.game.tricks);
+        }
+        assert_ne!(helper_actions(&single[0]), helper_actions(&single[1]));
+
+        let statistics = batch_statistics(config.clone(), helper_factory());
+        let again = batch_statistics(
+            BatchConfig {
+                threads: 3,
+                ..config
+            },
+            helper_factory(),
+        );
+        assert_eq!(statistics.players, again.players);
+        assert_eq!(statistics.ratings.ratings, again.ratings.ratings);
+    }
+
+    #[test]
+    fn test_batch_worker_panic() {
+        let factory: Arc<dyn AgentFactory> =
+            Arc::new(|seat: PlaceAtTable, seed: u64| -> Box<dyn Agent> {
+                if seed == 4 * 5 + seat.0 as u64 {
+                    panic!("no agent for game 5");
+                }
+                Box::new(RandomAgent::new(seed))
+            });
+        let config = BatchConfig {
+            games: 8,
+            threads: 2,
+            ..BatchConfig::default()
+        };
+        let run = run_batch(config, factory);
+        let panic = std::panic::catch_unwind(AssertUnwindSafe(|| collect_batch(run)));
+        let message = *panic.unwrap_err().downcast::<&str>().unwrap();
+        assert_eq!(message, "no agent for game 5");
+    }
+}
@@ -0,0 +1,313 @@
+use crate::game::cards::Suit;
+use crate::game::gameevent::{effective_events, ActionType, AnswerType, QuestionType};
+use crate::game::gamestate::GamePhase;
+use crate::game::player::PlaceAtTable;
+use crate::game::search::{card_from_index, card_index};
+use crate::game::Game;
+
+/*
+Features of a player's view, seats are relative to the viewer:
+0 is the viewer, 1 the next player, 2 the partner and 3 the previous player.
+ */
+pub const HAND: usize = 0;
+/// Cards each seat played in finished and current tricks
+pub const PLAYED: usize = HAND + 36;
+/// Cards each seat played in the current trick
+pub const TRICK: usize = PLAYED + 4 * 36;
+/// No trump and one per suit
+pub const TRUMP: usize = TRICK + 4 * 36;
+pub const CALLED: usize = TRUMP + 5;
+/// Last bid of each seat, scaled from 115 to 420 onto 0 to 1, then whether it stopped
+pub const BIDS: usize = CALLED + 4;
+pub const VALUE: usize = BIDS + 8;
+pub const PHASE: usize = VALUE + 1;
+pub const AT_TURN: usize = PHASE + PHASES;
+/// Cards the viewer passed, then cards it received
+pub const PASSED: usize = AT_TURN + 4;
+pub const FEATURES: usize = PASSED + 2 * 36;
+
+const PHASES: usize = 11;
+
+/*
+Every action has a stable index, passes are numbered by the combinadic of their card indices.
+ */
+const START: usize = 0;
+const STOP: usize = 1;
+const BID: usize = 2;
+const BID_VALUES: usize = 61;
+const PASS: usize = BID + BID_VALUES;
+const PASSES: usize = 58905;
+const CARD: usize = PASS + PASSES;
+const QUESTION_YOURS: usize = CARD + 36;
+const QUESTION_HALF: usize = QUESTION_YOURS + 1;
+const ANNOUNCE: usize = QUESTION_HALF + 4;
+const ANSWER_NO_PAIR: usize = ANNOUNCE + 4;
+const ANSWER_PAIR: usize = ANSWER_NO_PAIR + 1;
+const ANSWER_HALF: usize = ANSWER_PAIR + 4;
+const ANSWER_NO_HALF: usize = ANSWER_HALF + 4;
+const UNDO_REQUEST: usize = ANSWER_NO_HALF + 4;
+const UNDO_ACCEPT: usize = UNDO_REQUEST + 1;
+const UNDO_DECLINE: usize = UNDO_ACCEPT + 1;
+pub const ACTIONS: usize = UNDO_DECLINE + 1;
+
+const SUITS: [Suit; 4] = [Suit::Green, Suit::Acorns, Suit::Bells, Suit::Red];
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// Rank of a set of 4 distinct card indices among all such sets.
+fn combinadic(mut indices: [usize; 4]) -> usize {
+    indices.sort_unstable();
+    indices
+        .iter()
+        .enumerate()
+        .map(|(i, index)| binomial(*index, i + 1))
+        .sum()
+}
+
+fn from_combinadic(mut rank: usize) -> [usize; 4] {
+    let mut indices = [0; 4];
+    for k in (1..=4).rev() {
+        let mut index = k - 1;
+        while binomial(index + 1, k) <= rank {
+            index += 1;
+        }
+        rank -= binomial(index, k);
+        indices[k - 1] = index;
+    }
+    indices
+}
+
+/**
+ * Index of an action which can be legal.
+ * Panics for bids which are not a multiple of 5 from 120 to 420 and for passes
+ * which are not four distinct cards, see `try_action_index`.
+ */
+pub fn action_index(action: &ActionType) -> usize {
+    try_action_index(action)
+        .unwrap_or_else(|| panic!("{:?} is never legal and has no index", action))
+}
+
+/// Index of the action, `None` for actions which are never legal.
+pub fn try_action_index(action: &ActionType) -> Option<usize> {
+    Some(match action {
+        ActionType::Start => START,
+        ActionType::StopBidding => STOP,
+        ActionType::NewBid(value) => {
+            if !(120..=420).contains(value) || value % 5 != 0 {
+                return None;
+            }
+            BID + ((value - 120) / 5) as usize
+        }
+        ActionType::Pass(cards) => {
+            let indices: [usize; 4] = cards
+                .iter()
+                .map(|card| card_index(card) as usize)
+                .collect::<Vec<usize>>()
+                .try_into()
+                .ok()?;
+            if (1..4).any(|i| indices[..i].contains(&indices[i])) {
+                return None;
+            }
+            PASS + combinadic(indices)
+        }
+        ActionType::CardPlayed(card) => CARD + card_index(card) as usize,
+        ActionType::Question(QuestionType::Yours) => QUESTION_YOURS,
+        ActionType::Question(QuestionType::YourHalf(suit)) => QUESTION_HALF + *suit as usize,
+        ActionType::AnnounceTrump(suit) => ANNOUNCE + *suit as usize,
+        ActionType::Answer(AnswerType::NoPair) => ANSWER_NO_PAIR,
+        ActionType::Answer(AnswerType::YesPair(suit)) => ANSWER_PAIR + *suit as usize,
+        ActionType::Answer(AnswerType::YesHalf(suit)) => ANSWER_HALF + *suit as usize,
+        ActionType::Answer(AnswerType::NoHalf(suit)) => ANSWER_NO_HALF + *suit as usize,
+        ActionType::UndoRequest => UNDO_REQUEST,
+        ActionType::UndoAccept => UNDO_ACCEPT,
+        ActionType::UndoDecline => UNDO_DECLINE,
+    })
+}
+
+/// Inverse of `action_index`, passes are sorted as in legal actions.
+pub fn action_from_index(index: usize) -> Option<ActionType> {
+    let suit = |offset: usize| SUITS[index - offset];
+    Some(match index {
+        START => ActionType::Start,
+        STOP => ActionType::StopBidding,
+        _ if index < PASS => ActionType::NewBid(120 + 5 * (index - BID) as i32),
+        _ if index < CARD => {
+            let mut cards: Vec<_> = from_combinadic(index - PASS)
+                .iter()
+                .map(|i| card_from_index(*i as u8))
+                .collect();
+            cards.sort();
+            cards.reverse();
+            ActionType::Pass(cards)
+        }
+        _ if index < QUESTION_YOURS => {
+            ActionType::CardPlayed(card_from_index((index - CARD) as u8))
+        }
+        QUESTION_YOURS => ActionType::Question(QuestionType::Yours),
+        _ if index < ANNOUNCE => ActionType::Question(QuestionType::YourHalf(suit(QUESTION_HALF))),
+        _ if index < ANSWER_NO_PAIR => ActionType::AnnounceTrump(suit(ANNOUNCE)),
+        ANSWER_NO_PAIR => ActionType::Answer(AnswerType::NoPair),
+        _ if index < ANSWER_HALF => ActionType::Answer(AnswerType::YesPair(suit(ANSWER_PAIR))),
+        _ if index < ANSWER_NO_HALF => ActionType::Answer(AnswerType::YesHalf(suit(ANSWER_HALF))),
+        _ if index < UNDO_REQUEST => ActionType::Answer(AnswerType::NoHalf(suit(ANSWER_NO_HALF))),
+        UNDO_REQUEST => ActionType::UndoRequest,
+        UNDO_ACCEPT => ActionType::UndoAccept,
+        UNDO_DECLINE => ActionType::UndoDecline,
+        _ => return None,
+    })
+}
+
+/// Legal actions of a seat as a mask over all action indices.
+pub fn legal_mask(game: &Game, seat: &PlaceAtTable) -> Vec<bool> {
+    let mut mask = vec![false; ACTIONS];
+    for action in game.legal.iter().filter(|action| action.player == *seat) {
+        mask[action_index(&action.action_type)] = true;
+    }
+    mask
+}
+
+fn phase_index(phase: &GamePhase) -> usize {
+    match phase {
+        GamePhase::WaitingForStart => 0,
+        GamePhase::Bidding => 1,
+        GamePhase::PassingForth => 2,
+        GamePhase::PassingBack => 3,
+        GamePhase::Raising => 4,
+        GamePhase::StartTrick => 5,
+        GamePhase::Trick => 6,
+        GamePhase::AnsweringPair => 7,
+        GamePhase::AnsweringHalf(_) => 8,
+        GamePhase::Ended => 9,
+        GamePhase::PendingUndo(_) => 10,
+    }
+}
+
+fn scale_value(value: i32) -> f32 {
+    (value - 115) as f32 / 305.0
+}
+
+/**
+ * Everything the seat can know about the game as `FEATURES` numbers,
+ * see the offsets above for the layout.
+ */
+pub fn encode_view(game: &Game, seat: &PlaceAtTable) -> Vec<f32> {
+    let mut features = vec![0.0; FEATURES];
+    let relative = |place: &PlaceAtTable| ((place.0 + 4 - seat.0) % 4) as usize;
+    let state = &game.state;
+
+    for card in &state.player_at_place(seat.clone()).cards {
+        features[HAND + card_index(card) as usize] = 1.0;
+    }
+
+    let events = effective_events(&game.all_events);
+    let plays: Vec<_> = events
+        .iter()
+        .filter_map(|event| match &event.last_action.action_type {
+            ActionType::CardPlayed(card) => Some((relative(&event.last_action.player), card)),
+            _ => None,
+        })
+        .collect();
+    for (player, card) in &plays {
+        features[PLAYED + 36 * player + card_index(card) as usize] = 1.0;
+    }
+    let in_trick = match state.current_trick.len() {
+        4 => 0,
+        len => len,
+    };
+    for (player, card) in &plays[plays.len() - in_trick..] {
+        features[TRICK + 36 * player + card_index(card) as usize] = 1.0;
+    }
+    for event in &events {
+        if let ActionType::Pass(cards) = &event.last_action.action_type {
+            let offset = match relative(&event.last_action.player) {
+                0 => PASSED,
+                2 => PASSED + 36,
+                _ => continue,
+            };
+            for card in cards {
+                features[offset + card_index(card) as usize] = 1.0;
+            }
+        }
+    }
+
+    features[TRUMP + state.trump.map_or(0, |suit| suit as usize + 1)] = 1.0;
+    for suit in &state.trump_called {
+        features[CALLED + *suit as usize] = 1.0;
+    }
+    for (action, player) in &state.bidding_history {
+        let player = relative(player);
+        match action {
+            ActionType::NewBid(value) => features[BIDS + player] = scale_value(*value),
+            ActionType::StopBidding => features[BIDS + 4 + player] = 1.0,
+            _ => {}
+        }
+    }
+    features[VALUE] = scale_value(state.value.0);
+    features[PHASE + phase_index(&state.phase)] = 1.0;
+    features[AT_TURN + relative(&state.player_at_turn)] = 1.0;
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{next_decision, Agent, RandomAgent};
+
+    #[test]
+    fn test_action_index_round_trip() {
+        for index in 0..ACTIONS {
+            let action = action_from_index(index).unwrap();
+            assert_eq!(action_index(&action), index, "{:?}", action);
+        }
+        assert!(action_from_index(ACTIONS).is_none());
+        assert_eq!(PASSES, binomial(36, 4));
+
+        for value in [115, 122, 425, -5] {
+            assert_eq!(try_action_index(&ActionType::NewBid(value)), None);
+        }
+        assert_eq!(try_action_index(&ActionType::NewBid(420)), Some(PASS - 1));
+        let cards = |cards: &[&str]| cards.iter().map(|c| c.parse().unwrap()).collect();
+        assert_eq!(
+            try_action_index(&ActionType::Pass(cards(&["r-A", "g-6", "e-K"]))),
+            None
+        );
+        let twice = ActionType::Pass(cards(&["r-A", "g-6", "e-K", "r-A"]));
+        assert_eq!(try_action_index(&twice), None);
+    }
+
+    #[test]
+    fn test_encode_random_games() {
+        for seed in 0..5 {
+            let names = ["A", "B", "C", "D"].map(String::from);
+            let mut game = Game::new_seeded(String::from("Encoding"), names, seed);
+            let mut agent = RandomAgent::new(seed);
+            while let Some((seat, legal)) = next_decision(&game) {
+                let features = encode_view(&game, &seat);
+                assert_eq!(features.len(), FEATURES);
+                let hand: f32 = features[HAND..PLAYED].iter().sum();
+                assert_eq!(
+                    hand as usize,
+                    game.state.player_at_place(seat.clone()).cards.len()
+                );
+                if game.state.phase != GamePhase::WaitingForStart {
+                    assert_eq!(features[AT_TURN], 1.0);
+                }
+
+                let mask = legal_mask(&game, &seat);
+                let undo = game.legal.undo_request.as_ref() == Some(&seat);
+                assert_eq!(
+                    mask.iter().filter(|legal| **legal).count(),
+                    legal.len() + undo as usize
+                );
+                let action = agent.select_action(&game, &legal);
+                assert!(mask[action_index(&action.action_type)]);
+                game = game.apply_action(action).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+use std::path::Path;
+
+use crate::ai::agent::next_decision;
+use crate::ai::batch::BatchResult;
+use crate::ai::encoding::{action_index, encode_view, legal_mask, ACTIONS, FEATURES};
+use crate::archive::{GameArchive, GameQuery};
+use crate::game::errors::GameError;
+use crate::game::gameevent::{effective_events, ActionType};
+use crate::game::gameinfo::GameFinishedInfo;
+use crate::game::Game;
+
+/**
+ * Training samples as flat arrays: for every decision the acting seat's view,
+ * the index of the chosen action and the final score of its party.
+ * Legal action masks are large, they are only kept when asked for.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SampleSet {
+    pub features: Vec<f32>,
+    pub actions: Vec<i64>,
+    pub outcomes: Vec<f32>,
+    pub masks: Option<Vec<bool>>,
+}
+
+impl SampleSet {
+    pub fn new(with_masks: bool) -> Self {
+        SampleSet {
+            masks: with_masks.then(Vec::new),
+            ..SampleSet::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Replays the effective history, starts are no decisions and are skipped.
+    pub fn add_game(&mut self, finished: &GameFinishedInfo) -> Result<(), GameError> {
+        let mut game = Game::new(
+            finished.info.name.clone(),
+            finished.info.player_names.clone(),
+            Some(finished.info.player_start_cards.clone()),
+        );
+        let outcomes = [0, 1].map(|party| finished.party_score(party) as f32);
+        for event in effective_events(&finished.all_events) {
+            let action = event.last_action;
+            let decision = next_decision(&game).filter(|(seat, _)| *seat == action.player);
+            if decision.is_some() && action.action_type != ActionType::Start {
+                self.features.extend(encode_view(&game, &action.player));
+                self.actions.push(action_index(&action.action_type) as i64);
+                self.outcomes.push(outcomes[action.player.0 as usize % 2]);
+                if let Some(masks) = &mut self.masks {
+                    masks.extend(legal_mask(&game, &action.player));
+                }
+            }
+            game = game.apply_action(action)?;
+        }
+        Ok(())
+    }
+
+    pub fn add_archive(
+        &mut self,
+        archive: &dyn GameArchive,
+        query: &GameQuery,
+    ) -> Result<(), Error> {
+        for game in archive.query_games(query)? {
+            self.add_game(&game)
+                .map_err(|e| Error::other(format!("{:?} in '{}'", e, game.info.name)))?;
+        }
+        Ok(())
+    }
+
+    /// Self-play games, e.g. from `collect_batch`.
+    pub fn add_batch(&mut self, results: &[BatchResult]) -> Result<(), GameError> {
+        results
+            .iter()
+            .try_for_each(|result| self.add_game(&result.game))
+    }
+
+    /**
+     * Writes `features.npy` (float32, samples × FEATURES), `actions.npy` (int64),
+     * `outcomes.npy` (float32) and with masks `masks.npy` (bool, samples × ACTIONS) into the directory.
+     */
+    pub fn write_npy<P: AsRef<Path>>(&self, directory: P) -> Result<(), Error> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)?;
+        let create = |name: &str| File::create(directory.join(name)).map(BufWriter::new);
+        let n = self.len();
+
+        let bytes: Vec<u8> = self.features.iter().flat_map(|x| x.to_le_bytes()).collect();
+        write_npy(create("features.npy")?, "<f4", &[n, FEATURES], &bytes)?;
+        let bytes: Vec<u8> = self.actions.iter().flat_map(|x| x.to_le_bytes()).collect();
+        write_npy(create("actions.npy")?, "<i8", &[n], &bytes)?;
+        let bytes: Vec<u8> = self.outcomes.iter().flat_map(|x| x.to_le_bytes()).collect();
+        write_npy(create("outcomes.npy")?, "<f4", &[n], &bytes)?;
+        if let Some(masks) = &self.masks {
+            let bytes: Vec<u8> = masks.iter().map(|legal| *legal as u8).collect();
+            write_npy(create("masks.npy")?, "|b1", &[n, ACTIONS], &bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes an array in the NumPy `.npy` format version 1.0, `data` is in C order.
+pub fn write_npy<W: Write>(
+    mut writer: W,
+    descr: &str,
+    shape: &[usize],
+    data: &[u8],
+) -> Result<(), Error> {
+    let shape = match shape {
+        [n] => format!("({},)", n),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    // magic, version and length take 10 bytes, the header ends with a newline at a multiple of 64
+    let padding = 63 - (10 + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(data)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{Agent, RandomAgent};
+    use crate::ai::batch::{collect_batch, run_batch, AgentFactory, BatchConfig};
+    use crate::archive::MemoryArchive;
+    use crate::game::player::PlaceAtTable;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_write_npy_header() {
+        let mut buffer = vec![];
+        write_npy(&mut buffer, "<f4", &[2, 3], &[0; 24]).unwrap();
+        assert_eq!(buffer.len(), 128 + 24);
+        assert_eq!(&buffer[..8], b"\x93NUMPY\x01\x00");
+        let header = std::str::from_utf8(&buffer[10..128]).unwrap();
+        assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }"));
+        assert!(header.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_samples_from_batch_and_archive() {
+        let factory: Arc<dyn AgentFactory> =
+            Arc::new(|_: PlaceAtTable, seed: u64| -> Box<dyn Agent> {
+                Box::new(RandomAgent::new(seed))
+            });
+        let config = BatchConfig {
+            games: 3,
+            threads: 1,
+            ..BatchConfig::default()
+        };
+        let results = collect_batch(run_batch(config, factory));
+        let mut samples = SampleSet::new(true);
+        samples.add_batch(&results).unwrap();
+        assert!(!samples.is_empty());
+        assert_eq!(samples.features.len(), samples.len() * FEATURES);
+        assert_eq!(samples.outcomes.len(), samples.len());
+        let masks = samples.masks.as_ref().unwrap();
+        for (i, action) in samples.actions.iter().enumerate() {
+            assert!(masks[i * ACTIONS + *action as usize]);
+        }
+
+        let mut archive = MemoryArchive::new();
+        for result in &results {
+            archive.insert(result.game.clone()).unwrap();
+        }
+        let mut archived = SampleSet::new(false);
+        archived.add_archive(&archive, &GameQuery::new()).unwrap();
+        assert_eq!(archived.actions, samples.actions);
+        assert_eq!(archived.outcomes, samples.outcomes);
+        assert!(archived.masks.is_none());
+    }
+}
@@ -0,0 +1,287 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use crate::ai::agent::{heuristic_agents, play_to_end};
+use crate::game::cards::{get_all_cards, halves, pairs, Card, Suit, Value};
+use crate::game::gameevent::ActionType;
+use crate::game::gameinfo::GameFinishedInfo;
+use crate::game::player::PlaceAtTable;
+use crate::game::points::{points_pair, Points};
+use crate::game::Game;
+
+/// Deals which contradict the partner's signal are redrawn at most this often.
+const MAX_REDRAWS: usize = 200;
+
+#[derive(Debug, Clone)]
+pub struct HandEvalConfig {
+    /// Number of sampled deals for the other three hands
+    pub samples: usize,
+    /// Share of samples which have to reach the recommended bid
+    pub target_confidence: f64,
+    pub seed: u64,
+}
+
+impl Default for HandEvalConfig {
+    fn default() -> Self {
+        HandEvalConfig {
+            samples: 32,
+            target_confidence: 0.6,
+            seed: 0,
+        }
+    }
+}
+
+/**
+ * What the partner's bidding tells about their hand.
+ * A bid which skips at least one step is a jump and promises a pair.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PartnerSignal {
+    /// Partner did not bid yet
+    Unknown,
+    /// Partner stopped without bidding, no pair
+    Passed,
+    /// Partner bid the next step, an ace or a half
+    Bid,
+    /// Partner skipped values, a pair
+    Jump,
+}
+
+impl PartnerSignal {
+    fn allows(&self, partner_cards: &[Card]) -> bool {
+        let has_pair = !pairs(partner_cards.to_vec()).is_empty();
+        match self {
+            PartnerSignal::Unknown => true,
+            PartnerSignal::Passed => !has_pair,
+            PartnerSignal::Bid => {
+                !halves(partner_cards.to_vec()).is_empty()
+                    || partner_cards.iter().any(|c| c.value == Value::Ace)
+            }
+            PartnerSignal::Jump => has_pair,
+        }
+    }
+}
+
+/// The strongest signal the partner of `place` gave in the bidding so far.
+pub fn partner_signal(
+    bidding_history: &[(ActionType, PlaceAtTable)],
+    place: PlaceAtTable,
+) -> PartnerSignal {
+    let partner = place.partner();
+    let mut value = 115;
+    let mut signal = PartnerSignal::Unknown;
+    for (action, player) in bidding_history {
+        match action {
+            ActionType::NewBid(bid) => {
+                if *player == partner {
+                    let this = if *bid > value + 5 {
+                        PartnerSignal::Jump
+                    } else {
+                        PartnerSignal::Bid
+                    };
+                    if signal != PartnerSignal::Jump {
+                        signal = this;
+                    }
+                }
+                value = *bid;
+            }
+            ActionType::StopBidding if *player == partner && signal == PartnerSignal::Unknown => {
+                signal = PartnerSignal::Passed;
+            }
+            _ => {}
+        }
+    }
+    signal
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairPotential {
+    /// Suits with both ober and king
+    pub pairs: Vec<Suit>,
+    /// Suits with only one of them
+    pub halves: Vec<Suit>,
+    /// Sum of the pairs already in hand
+    pub pair_points: i32,
+    /// Pair points the party announced on average, including pairs completed with the partner
+    pub expected_pair_points: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HandEvaluation {
+    /// Trick points of the party when playing the hand, out of 140
+    pub expected_trick_points: f64,
+    pub pair_potential: PairPotential,
+    /// Trick and pair points together
+    pub expected_points: f64,
+    /// Highest value reached in enough samples, None if not even 120 is
+    pub recommended_max_bid: Option<i32>,
+    /// Share of samples which reached the recommended bid, or 120 if there is none
+    pub confidence: f64,
+}
+
+/**
+ * Estimates how well nine cards play as the playing party.
+ * The other hands are sampled to match the partner's signal and every deal is played out
+ * by heuristic agents, with this hand winning the bidding at 120.
+ */
+pub fn evaluate_hand(
+    cards: &[Card],
+    signal: PartnerSignal,
+    config: &HandEvalConfig,
+) -> HandEvaluation {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let rest: Vec<Card> = get_all_cards()
+        .into_iter()
+        .filter(|c| !cards.contains(c))
+        .collect();
+    let names = ["Hand", "Left", "Partner", "Right"].map(String::from);
+    let samples = config.samples.max(1);
+
+    let mut totals = Vec::with_capacity(samples);
+    let (mut trick_sum, mut pair_sum) = (0.0, 0.0);
+    for sample in 0..samples {
+        let mut others = rest.clone();
+        others.shuffle(&mut rng);
+        for _ in 0..MAX_REDRAWS {
+            if signal.allows(&others[9..18]) {
+                break;
+            }
+            others.shuffle(&mut rng);
+        }
+        let deal = [
+            cards.to_vec(),
+            others[..9].to_vec(),
+            others[9..18].to_vec(),
+            others[18..].to_vec(),
+        ];
+        let game = Game::new(String::from("Evaluation"), names.clone(), Some(deal))
+            .apply_notation("start; start; start; start; bid 120; stop; stop; stop")
+            .expect("opening the bidding is always legal");
+        let seed = config.seed.wrapping_add(sample as u64 * 4);
+        let mut agents = heuristic_agents(seed);
+        let finished = GameFinishedInfo::from(play_to_end(game, &mut agents));
+
+        let points = finished.players_points();
+        let announced = finished.players_announced();
+        let pair_points: i32 = announced[0]
+            .iter()
+            .chain(&announced[2])
+            .map(|suit| points_pair(*suit).0)
+            .sum();
+        let total = points[0].0 + points[2].0;
+        trick_sum += (total - pair_points) as f64;
+        pair_sum += pair_points as f64;
+        totals.push(total);
+    }
+
+    totals.sort_unstable_by(|a, b| b.cmp(a));
+    let reached =
+        |value: i32| totals.iter().filter(|t| **t >= value).count() as f64 / samples as f64;
+    let needed = ((config.target_confidence * samples as f64).ceil() as usize).clamp(1, samples);
+    let recommended = (totals[needed - 1] / 5 * 5).min(420);
+    let recommended_max_bid = (recommended >= 120).then_some(recommended);
+
+    let own_pairs = pairs(cards.to_vec());
+    HandEvaluation {
+        expected_trick_points: trick_sum / samples as f64,
+        pair_potential: PairPotential {
+            pair_points: own_pairs.iter().map(|s| points_pair(*s).0).sum(),
+            halves: halves(cards.to_vec())
+                .into_iter()
+                .filter(|s| !own_pairs.contains(s))
+                .collect(),
+            pairs: own_pairs,
+            expected_pair_points: pair_sum / samples as f64,
+        },
+        expected_points: (trick_sum + pair_sum) / samples as f64,
+        recommended_max_bid,
+        confidence: reached(recommended_max_bid.unwrap_or(120)),
+    }
+}
+
+impl Game {
+    /// Evaluates the hand of a seat with the signals its partner gave so far.
+    pub fn evaluate_hand(&self, place: PlaceAtTable, config: &HandEvalConfig) -> HandEvaluation {
+        let signal = partner_signal(&self.state.bidding_history, place.clone());
+        evaluate_hand(&self.state.player_at_place(place).cards, signal, config)
+    }
+
+    /// Whether the evaluation supports the next bid at the current value.
+    pub fn bid_is_supported(&self, place: PlaceAtTable, config: &HandEvalConfig) -> bool {
+        let next = self.state.value + Points(5);
+        self.evaluate_hand(place, config)
+            .recommended_max_bid
+            .is_some_and(|max| max >= next.0)
+    }
+}
+
+/// Cards in notation for tests, e.g. `["r-A", "g-6"]`.
+#[cfg(test)]
+pub(crate) fn helper_cards(cards: &[&str]) -> Vec<Card> {
+    cards.iter().map(|c| c.parse().unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partner_signal() {
+        let bids = |actions: &[(ActionType, u8)]| -> Vec<(ActionType, PlaceAtTable)> {
+            actions
+                .iter()
+                .map(|(a, p)| (a.clone(), PlaceAtTable(*p)))
+                .collect()
+        };
+        let history = bids(&[(ActionType::NewBid(120), 0), (ActionType::NewBid(125), 1)]);
+        assert_eq!(
+            partner_signal(&history, PlaceAtTable(3)),
+            PartnerSignal::Bid
+        );
+        assert_eq!(
+            partner_signal(&history, PlaceAtTable(1)),
+            PartnerSignal::Unknown
+        );
+        let history = bids(&[(ActionType::NewBid(120), 0), (ActionType::NewBid(140), 1)]);
+        assert_eq!(
+            partner_signal(&history, PlaceAtTable(3)),
+            PartnerSignal::Jump
+        );
+        let history = bids(&[(ActionType::StopBidding, 0)]);
+        assert_eq!(
+            partner_signal(&history, PlaceAtTable(2)),
+            PartnerSignal::Passed
+        );
+    }
+
+    #[test]
+    fn test_strong_hand_bids_higher() {
+        let config = HandEvalConfig {
+            samples: 12,
+            ..HandEvalConfig::default()
+        };
+        let weak = helper_cards(&[
+            "g-6", "g-7", "e-6", "e-7", "s-6", "s-7", "r-6", "r-7", "g-8",
+        ]);
+        let strong = helper_cards(&[
+            "r-O", "r-K", "s-O", "s-K", "g-A", "e-A", "s-A", "r-A", "r-Z",
+        ]);
+        let weak_eval = evaluate_hand(&weak, PartnerSignal::Unknown, &config);
+        let strong_eval = evaluate_hand(&strong, PartnerSignal::Unknown, &config);
+
+        assert_eq!(
+            strong_eval.pair_potential.pairs,
+            vec![Suit::Bells, Suit::Red]
+        );
+        assert_eq!(strong_eval.pair_potential.pair_points, 180);
+        assert!(weak_eval.pair_potential.pairs.is_empty());
+        assert!(strong_eval.expected_points > weak_eval.expected_points);
+        assert!(
+            strong_eval.recommended_max_bid.unwrap() > weak_eval.recommended_max_bid.unwrap_or(0)
+        );
+        assert!(strong_eval.confidence >= config.target_confidence);
+        assert!(strong_eval.expected_trick_points <= 140.0);
+    }
+}
@@ -0,0 +1,227 @@
+use std::io::Error;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tract_onnx::prelude::*;
+
+use crate::ai::agent::Agent;
+use crate::ai::encoding::{action_index, encode_view, ACTIONS, FEATURES};
+use crate::game::gameevent::GameAction;
+use crate::game::player::PlaceAtTable;
+use crate::game::Game;
+
+type Model = TypedRunnableModel<TypedModel>;
+
+/**
+ * Plays with a trained network on the CPU.
+ * The model gets the acting seat's view from `encode_view` as a `[1, FEATURES]` float tensor
+ * and returns `[1, ACTIONS]` logits indexed by `action_index`, a second output is read as value.
+ * Illegal actions are masked, then the best action is taken or one is sampled.
+ */
+pub struct NetworkAgent {
+    model: Model,
+    rng: StdRng,
+    /// Sampling temperature, `None` always takes the best action
+    temperature: Option<f32>,
+    /// Why the model could not be evaluated the last time it failed
+    last_error: Option<Error>,
+}
+
+/// Policy logits for every action index and the value output if the model has one.
+#[derive(Debug, Clone)]
+pub struct NetworkOutput {
+    pub logits: Vec<f32>,
+    pub value: Option<f32>,
+}
+
+impl NetworkAgent {
+    /// Loads an ONNX model file, the agent takes the best legal action.
+    pub fn load<P: AsRef<Path>>(path: P, seed: u64) -> Result<Self, Error> {
+        let model = tract_onnx::onnx()
+            .model_for_path(path)
+            .and_then(|model| model.with_input_fact(0, f32::fact([1, FEATURES]).into()))
+            .and_then(|model| model.into_optimized())
+            .map_err(Error::other)?;
+        Self::from_model(model, seed)
+    }
+
+    pub fn from_model(model: TypedModel, seed: u64) -> Result<Self, Error> {
+        Ok(NetworkAgent {
+            model: model.into_runnable().map_err(Error::other)?,
+            rng: StdRng::seed_from_u64(seed),
+            temperature: None,
+            last_error: None,
+        })
+    }
+
+    /// Samples from the softmax of the legal logits divided by the temperature.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn evaluate(&self, game: &Game, seat: &PlaceAtTable) -> Result<NetworkOutput, Error> {
+        let features = encode_view(game, seat);
+        let input = Tensor::from_shape(&[1, FEATURES], &features).map_err(Error::other)?;
+        let outputs = self.model.run(tvec!(input.into())).map_err(Error::other)?;
+        let logits = outputs[0].as_slice::<f32>().map_err(Error::other)?.to_vec();
+        if logits.len() != ACTIONS {
+            return Err(Error::other(format!(
+                "model returned {} logits, expected {}",
+                logits.len(),
+                ACTIONS
+            )));
+        }
+        let value = match outputs.get(1) {
+            Some(value) => value
+                .as_slice::<f32>()
+                .map_err(Error::other)?
+                .first()
+                .copied(),
+            None => None,
+        };
+        Ok(NetworkOutput { logits, value })
+    }
+
+    /// The error of the last failed evaluation in `select_action`.
+    pub fn last_error(&self) -> Option<&Error> {
+        self.last_error.as_ref()
+    }
+
+    /// Chooses from the legal actions, fails if the model cannot be evaluated.
+    pub fn try_select_action(
+        &mut self,
+        game: &Game,
+        legal: &[GameAction],
+    ) -> Result<GameAction, Error> {
+        let output = self.evaluate(game, &legal[0].player)?;
+        let logits: Vec<f32> = legal
+            .iter()
+            .map(|action| output.logits[action_index(&action.action_type)])
+            .collect();
+        Ok(legal[self.choose(&logits)].clone())
+    }
+
+    /// NaN logits count as impossible, if none is finite the first best action is taken.
+    fn choose(&mut self, logits: &[f32]) -> usize {
+        let logits: Vec<f32> = logits
+            .iter()
+            .map(|logit| match logit.is_nan() {
+                true => f32::NEG_INFINITY,
+                false => *logit,
+            })
+            .collect();
+        let best_index = (0..logits.len()).fold(0, |best, i| match logits[i] > logits[best] {
+            true => i,
+            false => best,
+        });
+        let best = logits[best_index];
+        let Some(temperature) = self.temperature.filter(|t| *t > 0.0 && best.is_finite()) else {
+            return best_index;
+        };
+        let weights: Vec<f32> = logits
+            .iter()
+            .map(|logit| ((logit - best) / temperature).exp())
+            .collect();
+        let mut target = self.rng.random::<f32>() * weights.iter().sum::<f32>();
+        for (i, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                return i;
+            }
+            target -= weight;
+        }
+        weights.len() - 1
+    }
+}
+
+impl Agent for NetworkAgent {
+    /// Falls back to the first legal action if the model cannot be evaluated, see `last_error`.
+    fn select_action(&mut self, game: &Game, legal: &[GameAction]) -> GameAction {
+        match self.try_select_action(game, legal) {
+            Ok(action) => action,
+            Err(e) => {
+                self.last_error = Some(e);
+                legal[0].clone()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{next_decision, play_to_end};
+    use crate::game::gameevent::ActionType;
+    use crate::game::gamestate::GamePhase;
+
+    /// Ignores its input and always returns the same logits.
+    fn helper_model(logits: Vec<f32>) -> TypedModel {
+        let mut model = TypedModel::default();
+        model.add_source("view", f32::fact([1, FEATURES])).unwrap();
+        let logits = Tensor::from_shape(&[1, ACTIONS], &logits).unwrap();
+        let output = model.add_const("logits", logits).unwrap();
+        model.set_output_outlets(&[output]).unwrap();
+        model
+    }
+
+    #[test]
+    fn test_network_agent() {
+        // prefers higher bids, then the lowest card index
+        let logits: Vec<f32> = (0..ACTIONS)
+            .map(|index| match index {
+                2..=62 => index as f32,
+                _ => -(index as f32),
+            })
+            .collect();
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let game = Game::new_seeded(String::from("Network"), names, 1)
+            .apply_notation("start; start; start; start")
+            .unwrap();
+        let mut agent = NetworkAgent::from_model(helper_model(logits.clone()), 0).unwrap();
+        let output = agent.evaluate(&game, &PlaceAtTable(0)).unwrap();
+        assert_eq!(output.logits, logits);
+        assert_eq!(output.value, None);
+
+        let (_, legal) = next_decision(&game).unwrap();
+        let action = agent.select_action(&game, &legal);
+        assert_eq!(action.action_type, ActionType::NewBid(420));
+
+        let sampling = NetworkAgent::from_model(helper_model(logits), 1)
+            .unwrap()
+            .with_temperature(10.0);
+        let mut agents: [Box<dyn Agent>; 4] = [
+            Box::new(agent),
+            Box::new(sampling),
+            Box::new(NetworkAgent::from_model(helper_model(vec![0.0; ACTIONS]), 2).unwrap()),
+            Box::new(NetworkAgent::from_model(helper_model(vec![0.0; ACTIONS]), 3).unwrap()),
+        ];
+        let finished = play_to_end(game, &mut agents);
+        assert_eq!(finished.state.phase, GamePhase::Ended);
+    }
+
+    #[test]
+    fn test_network_agent_failures() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let game = Game::new_seeded(String::from("Network"), names, 1)
+            .apply_notation("start; start; start; start")
+            .unwrap();
+        let (_, legal) = next_decision(&game).unwrap();
+
+        let mut nan = NetworkAgent::from_model(helper_model(vec![f32::NAN; ACTIONS]), 0)
+            .unwrap()
+            .with_temperature(1.0);
+        assert_eq!(nan.try_select_action(&game, &legal).unwrap(), legal[0]);
+
+        let mut model = TypedModel::default();
+        model.add_source("view", f32::fact([1, FEATURES])).unwrap();
+        let logits = Tensor::from_shape(&[1, 3], &[0.0f32; 3]).unwrap();
+        let output = model.add_const("logits", logits).unwrap();
+        model.set_output_outlets(&[output]).unwrap();
+        let mut broken = NetworkAgent::from_model(model, 0).unwrap();
+        assert!(broken.try_select_action(&game, &legal).is_err());
+        assert!(broken.last_error().is_none());
+        assert_eq!(broken.select_action(&game, &legal), legal[0]);
+        assert!(broken.last_error().is_some());
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+use crate::ai::agent::{heuristic_agents, is_half, play_to_end};
+use crate::game::cards::{pairs, Card, Suit, Value};
+use crate::game::gameevent::{ActionType, GameAction};
+use crate::game::gameinfo::GameFinishedInfo;
+use crate::game::gamestate::GamePhase;
+use crate::game::points::points_pair;
+use crate::game::Game;
+
+#[derive(Debug, Clone)]
+pub struct PassAdvisorConfig {
+    /// How many of the best passes by heuristic are refined with rollouts
+    pub refine: usize,
+    /// Sampled deals per refined pass
+    pub rollouts: usize,
+    pub seed: u64,
+}
+
+impl Default for PassAdvisorConfig {
+    fn default() -> Self {
+        PassAdvisorConfig {
+            refine: 6,
+            rollouts: 8,
+            seed: 0,
+        }
+    }
+}
+
+/// Why a pass is considered good or bad.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum PassReason {
+    GivesAces(usize),
+    GivesTens(usize),
+    /// A half the playing player may complete to a pair
+    GivesHalf(Suit),
+    /// Both halves of a suit go to the other hand
+    GivesPair(Suit),
+    /// The pair stays together in the playing player's hand
+    KeepsPair(Suit),
+    /// One half of a pair in the playing player's hand is given away
+    BreaksPair(Suit),
+    /// No card of the suit is left in the own hand
+    CreatesVoid(Suit),
+    GivesLowCards(usize),
+}
+
+impl fmt::Display for PassReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PassReason::GivesAces(n) => write!(f, "gives {} ace(s)", n),
+            PassReason::GivesTens(n) => write!(f, "gives {} ten(s)", n),
+            PassReason::GivesHalf(suit) => write!(f, "gives a half in {}", suit),
+            PassReason::GivesPair(suit) => write!(f, "gives the pair in {}", suit),
+            PassReason::KeepsPair(suit) => write!(f, "keeps the pair in {}", suit),
+            PassReason::BreaksPair(suit) => write!(f, "breaks the pair in {}", suit),
+            PassReason::CreatesVoid(suit) => write!(f, "leaves no {} in hand", suit),
+            PassReason::GivesLowCards(n) => write!(f, "gives {} low card(s)", n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PassCandidate {
+    pub action: GameAction,
+    /// Score from the rules of thumb alone
+    pub heuristic: f64,
+    /// Points of the playing party averaged over sampled deals, only for refined passes
+    pub expected_points: Option<f64>,
+    pub reasons: Vec<PassReason>,
+}
+
+impl PassCandidate {
+    pub fn cards(&self) -> &[Card] {
+        match &self.action.action_type {
+            ActionType::Pass(cards) => cards,
+            _ => &[],
+        }
+    }
+
+    /// Human readable summary of the reasons.
+    pub fn explanation(&self) -> String {
+        self.reasons
+            .iter()
+            .map(|reason| reason.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Rules of thumb for passing forth (to the playing player) or back (to the partner).
+fn score_pass(hand: &[Card], pass: &[Card], forth: bool) -> (f64, Vec<PassReason>) {
+    let mut score = 0.0;
+    let mut reasons = vec![];
+    let kept: Vec<Card> = hand.iter().filter(|c| !pass.contains(c)).cloned().collect();
+    let count = |value: Value| pass.iter().filter(|c| c.value == value).count();
+
+    let (aces, tens) = (count(Value::Ace), count(Value::Ten));
+    let low = pass.iter().filter(|c| c.value <= Value::Unter).count();
+    if forth {
+        score += 30.0 * aces as f64 + 15.0 * tens as f64;
+    } else {
+        score += -30.0 * aces as f64 - 15.0 * tens as f64 + 5.0 * low as f64;
+    }
+    if aces > 0 {
+        reasons.push(PassReason::GivesAces(aces));
+    }
+    if tens > 0 {
+        reasons.push(PassReason::GivesTens(tens));
+    }
+    if !forth && low > 0 {
+        reasons.push(PassReason::GivesLowCards(low));
+    }
+
+    let own_pairs = pairs(hand.to_vec());
+    for suit in Suit::iter() {
+        let given_halves = pass.iter().filter(|c| c.suit == suit && is_half(c)).count();
+        let pair = points_pair(suit).0 as f64;
+        match (forth, given_halves, own_pairs.contains(&suit)) {
+            (true, 2, _) => {
+                score += pair;
+                reasons.push(PassReason::GivesPair(suit));
+            }
+            (true, 1, _) => {
+                score += 20.0;
+                reasons.push(PassReason::GivesHalf(suit));
+            }
+            (false, 0, true) => {
+                score += pair / 2.0;
+                reasons.push(PassReason::KeepsPair(suit));
+            }
+            (false, _, true) => {
+                score -= pair;
+                reasons.push(PassReason::BreaksPair(suit));
+            }
+            _ => {}
+        }
+        let had_suit = hand.iter().any(|c| c.suit == suit);
+        if had_suit && !kept.iter().any(|c| c.suit == suit) {
+            score += if forth { 5.0 } else { 15.0 };
+            reasons.push(PassReason::CreatesVoid(suit));
+        }
+    }
+    (score, reasons)
+}
+
+/**
+ * Hands of the other seats are redistributed among them, keeping their sizes.
+ * Only the hand of the seat at turn is known to it.
+ */
+fn sample_deal(game: &Game, rng: &mut StdRng) -> Game {
+    let me = game.state.player_at_turn.0 as usize;
+    let mut hidden: Vec<Card> = (0..4)
+        .filter(|seat| *seat != me)
+        .flat_map(|seat| game.state.players[seat].cards.clone())
+        .collect();
+    hidden.shuffle(rng);
+    let mut sampled = game.clone();
+    for seat in (0..4).filter(|seat| *seat != me) {
+        let size = sampled.state.players[seat].cards.len();
+        sampled.state.players[seat].cards = hidden.split_off(hidden.len() - size);
+    }
+    sampled
+}
+
+fn rollout_points(game: &Game, action: &GameAction, config: &PassAdvisorConfig) -> f64 {
+    let party = (action.player.0 % 2) as usize;
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let rollouts = config.rollouts.max(1);
+    let total: f64 = (0..rollouts)
+        .map(|rollout| {
+            let next = sample_deal(game, &mut rng)
+                .apply_action(action.clone())
+                .expect("only legal passes are evaluated");
+            let seed = config.seed.wrapping_add(rollout as u64 * 4);
+            let mut agents = heuristic_agents(seed);
+            let points = GameFinishedInfo::from(play_to_end(next, &mut agents)).players_points();
+            (points[party].0 + points[party + 2].0) as f64
+        })
+        .sum();
+    total / rollouts as f64
+}
+
+/**
+ * All legal passes of the seat at turn, best first.
+ * Passes are scored by rules of thumb, the best of them are then compared by playing
+ * sampled deals to the end. Refined passes come first, ordered by expected points.
+ * Outside of the passing phases the list is empty.
+ */
+pub fn rank_passes(game: &Game, config: &PassAdvisorConfig) -> Vec<PassCandidate> {
+    let forth = match game.state.phase {
+        GamePhase::PassingForth => true,
+        GamePhase::PassingBack => false,
+        _ => return vec![],
+    };
+    let hand = &game.state.player_at_turn().cards;
+    let mut candidates: Vec<PassCandidate> = game
+        .legal
+        .iter()
+        .filter_map(|action| match action.action_type {
+            ActionType::Pass(ref cards) => {
+                let (heuristic, reasons) = score_pass(hand, cards, forth);
+                Some(PassCandidate {
+                    action,
+                    heuristic,
+                    expected_points: None,
+                    reasons,
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.heuristic.total_cmp(&a.heuristic));
+
+    let refined = config.refine.min(candidates.len());
+    for candidate in &mut candidates[..refined] {
+        candidate.expected_points = Some(rollout_points(game, &candidate.action, config));
+    }
+    candidates[..refined].sort_by(|a, b| {
+        b.expected_points
+            .unwrap()
+            .total_cmp(&a.expected_points.unwrap())
+            .then_with(|| b.heuristic.total_cmp(&a.heuristic))
+    });
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::hand_eval::helper_cards;
+
+    #[test]
+    fn test_score_pass() {
+        let hand = helper_cards(&[
+            "r-O", "r-K", "g-A", "g-6", "e-Z", "e-7", "s-6", "s-7", "s-8",
+        ]);
+        let (forth, reasons) =
+            score_pass(&hand, &helper_cards(&["r-O", "r-K", "g-A", "e-Z"]), true);
+        assert!(reasons.contains(&PassReason::GivesPair(Suit::Red)));
+        assert!(reasons.contains(&PassReason::GivesAces(1)));
+        let (low, _) = score_pass(&hand, &helper_cards(&["s-6", "s-7", "s-8", "g-6"]), true);
+        assert!(forth > low);
+
+        let (keep, reasons) =
+            score_pass(&hand, &helper_cards(&["s-6", "s-7", "s-8", "e-7"]), false);
+        assert!(reasons.contains(&PassReason::KeepsPair(Suit::Red)));
+        assert!(reasons.contains(&PassReason::CreatesVoid(Suit::Bells)));
+        let (breaking, reasons) =
+            score_pass(&hand, &helper_cards(&["r-O", "s-7", "s-8", "e-7"]), false);
+        assert!(reasons.contains(&PassReason::BreaksPair(Suit::Red)));
+        assert!(keep > breaking);
+    }
+
+    #[test]
+    fn test_rank_passes() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let game = Game::new_seeded(String::from("Passing"), names, 3)
+            .apply_notation("start; start; start; start; bid 120; stop; stop; stop")
+            .unwrap();
+        assert_eq!(game.state.phase, GamePhase::PassingForth);
+        let config = PassAdvisorConfig {
+            refine: 3,
+            rollouts: 2,
+            ..PassAdvisorConfig::default()
+        };
+        let ranked = rank_passes(&game, &config);
+        assert_eq!(ranked.len(), 126);
+        assert!(ranked[..3].iter().all(|c| c.expected_points.is_some()));
+        assert!(ranked[3..].iter().all(|c| c.expected_points.is_none()));
+        assert!(ranked[3..]
+            .windows(2)
+            .all(|w| w[0].heuristic >= w[1].heuristic));
+        assert_eq!(ranked[0].cards().len(), 4);
+
+        let game = game.apply_action(ranked[0].action.clone()).unwrap();
+        let ranked = rank_passes(&game, &config);
+        assert_eq!(ranked.len(), 715);
+        assert!(rank_passes(
+            &game.apply_action(ranked[0].action.clone()).unwrap(),
+            &config
+        )
+        .is_empty());
+    }
+}
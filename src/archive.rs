@@ -0,0 +1,521 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Error, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::game::cards::Suit;
+use crate::game::gameevent::{effective_events, GameCallback};
+use crate::game::gameinfo::GameFinishedInfo;
+
+/// Position of a game in its archive.
+pub type GameId = usize;
+
+/// Storage for finished games, see `MemoryArchive` and `FileArchive`.
+pub trait GameArchive {
+    fn insert(&mut self, game: GameFinishedInfo) -> Result<GameId, Error>;
+
+    fn get(&self, id: GameId) -> Result<Option<GameFinishedInfo>, Error>;
+
+    fn index(&self) -> &ArchiveIndex;
+
+    fn len(&self) -> usize {
+        self.index().entries.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn query(&self, query: &GameQuery) -> Vec<GameId> {
+        self.index().query(query)
+    }
+
+    /// Loads all games matching the query.
+    fn query_games(&self, query: &GameQuery) -> Result<Vec<GameFinishedInfo>, Error> {
+        self.query(query)
+            .into_iter()
+            .filter_map(|id| self.get(id).transpose())
+            .collect()
+    }
+}
+
+/// Which side of the game a queried player has to be on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Role {
+    #[default]
+    Any,
+    Playing,
+    Defending,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Won,
+    Lost,
+    NoOnePlayed,
+}
+
+/**
+ * Filter for archived games, every set field has to match.
+ * The result is seen from the queried player's party, or from the playing party without a player.
+ *
+ * ```
+ * use marjapussi::archive::GameQuery;
+ * // all games where Alice played 200+ and lost
+ * let query = GameQuery::new().player("Alice").playing().min_value(200).lost();
+ * ```
+ */
+#[derive(Debug, Clone, Default)]
+pub struct GameQuery {
+    pub player: Option<String>,
+    pub role: Role,
+    /// Dates as `YYYY-MM-DD`, both inclusive
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub min_value: Option<i32>,
+    pub max_value: Option<i32>,
+    pub trump: Option<Suit>,
+    pub result: Option<GameResult>,
+    pub schwarz: Option<bool>,
+}
+
+impl GameQuery {
+    pub fn new() -> Self {
+        GameQuery::default()
+    }
+
+    pub fn player(mut self, name: &str) -> Self {
+        self.player = Some(name.to_string());
+        self
+    }
+
+    pub fn playing(mut self) -> Self {
+        self.role = Role::Playing;
+        self
+    }
+
+    pub fn defending(mut self) -> Self {
+        self.role = Role::Defending;
+        self
+    }
+
+    pub fn from_date(mut self, date: &str) -> Self {
+        self.from_date = Some(date.to_string());
+        self
+    }
+
+    pub fn to_date(mut self, date: &str) -> Self {
+        self.to_date = Some(date.to_string());
+        self
+    }
+
+    pub fn min_value(mut self, value: i32) -> Self {
+        self.min_value = Some(value);
+        self
+    }
+
+    pub fn max_value(mut self, value: i32) -> Self {
+        self.max_value = Some(value);
+        self
+    }
+
+    pub fn trump(mut self, suit: Suit) -> Self {
+        self.trump = Some(suit);
+        self
+    }
+
+    pub fn won(mut self) -> Self {
+        self.result = Some(GameResult::Won);
+        self
+    }
+
+    pub fn lost(mut self) -> Self {
+        self.result = Some(GameResult::Lost);
+        self
+    }
+
+    pub fn no_one_played(mut self) -> Self {
+        self.result = Some(GameResult::NoOnePlayed);
+        self
+    }
+
+    pub fn schwarz(mut self, schwarz: bool) -> Self {
+        self.schwarz = Some(schwarz);
+        self
+    }
+}
+
+/// Everything queries can filter by, for one game.
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    pub players: [String; 4],
+    pub date: String,
+    pub value: i32,
+    pub trumps: Vec<Suit>,
+    /// Seat of the playing player's party, 0 or 1
+    pub playing_party: Option<u8>,
+    pub won: Option<bool>,
+    pub schwarz: bool,
+}
+
+impl IndexEntry {
+    pub fn from_game(game: &GameFinishedInfo) -> Self {
+        let time = game
+            .info
+            .start_time
+            .as_ref()
+            .unwrap_or(&game.info.create_time);
+        // undone announcements do not count
+        let trumps = effective_events(&game.all_events)
+            .into_iter()
+            .filter_map(|event| match event.callback {
+                Some(GameCallback::NewTrump(suit)) => Some(suit),
+                _ => None,
+            })
+            .collect();
+        IndexEntry {
+            players: game.info.player_names.clone(),
+            date: time.chars().take(10).collect(),
+            value: game.game_value.0,
+            trumps,
+            playing_party: game.playing_party.as_ref().map(|party| party.0 % 2),
+            won: game.won,
+            schwarz: game.schwarz_game,
+        }
+    }
+
+    pub fn matches(&self, query: &GameQuery) -> bool {
+        let seat = match &query.player {
+            Some(name) => match self.players.iter().position(|p| p == name) {
+                Some(seat) => Some(seat as u8),
+                None => return false,
+            },
+            None => None,
+        };
+        let in_playing_party = match (seat, self.playing_party) {
+            (Some(seat), Some(party)) => Some(seat % 2 == party),
+            _ => None,
+        };
+        let role_matches = match query.role {
+            Role::Any => true,
+            Role::Playing => in_playing_party == Some(true),
+            Role::Defending => in_playing_party == Some(false),
+        };
+        let result = match (self.won, in_playing_party) {
+            (None, _) => GameResult::NoOnePlayed,
+            // defenders win when the playing party loses
+            (Some(true), Some(false)) => GameResult::Lost,
+            (Some(false), Some(false)) => GameResult::Won,
+            (Some(true), _) => GameResult::Won,
+            (Some(false), _) => GameResult::Lost,
+        };
+        role_matches
+            && query.result.is_none_or(|r| r == result)
+            && query.min_value.is_none_or(|v| self.value >= v)
+            && query.max_value.is_none_or(|v| self.value <= v)
+            && query.from_date.as_ref().is_none_or(|d| self.date >= *d)
+            && query.to_date.as_ref().is_none_or(|d| self.date <= *d)
+            && query.trump.is_none_or(|t| self.trumps.contains(&t))
+            && query.schwarz.is_none_or(|s| self.schwarz == s)
+    }
+}
+
+/// In-memory indexes over all games of an archive.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveIndex {
+    pub entries: Vec<IndexEntry>,
+    by_player: HashMap<String, Vec<GameId>>,
+    by_date: BTreeMap<String, Vec<GameId>>,
+    by_value: BTreeMap<i32, Vec<GameId>>,
+    by_trump: HashMap<Suit, Vec<GameId>>,
+}
+
+impl ArchiveIndex {
+    pub fn add(&mut self, game: &GameFinishedInfo) -> GameId {
+        let id = self.entries.len();
+        let entry = IndexEntry::from_game(game);
+        for name in entry.players.iter().collect::<HashSet<_>>() {
+            self.by_player.entry(name.clone()).or_default().push(id);
+        }
+        self.by_date.entry(entry.date.clone()).or_default().push(id);
+        self.by_value.entry(entry.value).or_default().push(id);
+        for suit in entry.trumps.iter().collect::<HashSet<_>>() {
+            self.by_trump.entry(*suit).or_default().push(id);
+        }
+        self.entries.push(entry);
+        id
+    }
+
+    /// Narrows the candidates with the most specific index, then checks every filter.
+    pub fn query(&self, query: &GameQuery) -> Vec<GameId> {
+        let collect = |ids: Vec<&Vec<GameId>>| -> Vec<GameId> {
+            let mut ids: Vec<GameId> = ids.into_iter().flatten().copied().collect();
+            ids.sort();
+            ids
+        };
+        let candidates: Vec<GameId> = if let Some(name) = &query.player {
+            self.by_player.get(name).cloned().unwrap_or_default()
+        } else if let Some(suit) = query.trump {
+            self.by_trump.get(&suit).cloned().unwrap_or_default()
+        } else if query.min_value.is_some() || query.max_value.is_some() {
+            let range = query.min_value.unwrap_or(i32::MIN)..=query.max_value.unwrap_or(i32::MAX);
+            collect(self.by_value.range(range).map(|(_, ids)| ids).collect())
+        } else if query.from_date.is_some() || query.to_date.is_some() {
+            let from = query.from_date.clone().unwrap_or_default();
+            collect(
+                self.by_date
+                    .range(from..)
+                    .filter(|(date, _)| query.to_date.as_ref().is_none_or(|to| *date <= to))
+                    .map(|(_, ids)| ids)
+                    .collect(),
+            )
+        } else {
+            (0..self.entries.len()).collect()
+        };
+        candidates
+            .into_iter()
+            .filter(|id| self.entries[*id].matches(query))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryArchive {
+    games: Vec<GameFinishedInfo>,
+    index: ArchiveIndex,
+}
+
+impl MemoryArchive {
+    pub fn new() -> Self {
+        MemoryArchive::default()
+    }
+}
+
+impl GameArchive for MemoryArchive {
+    fn insert(&mut self, game: GameFinishedInfo) -> Result<GameId, Error> {
+        let id = self.index.add(&game);
+        self.games.push(game);
+        Ok(id)
+    }
+
+    fn get(&self, id: GameId) -> Result<Option<GameFinishedInfo>, Error> {
+        Ok(self.games.get(id).cloned())
+    }
+
+    fn index(&self) -> &ArchiveIndex {
+        &self.index
+    }
+}
+
+/**
+ * Archive in a file with one JSON game per line, the same as `parse --ndjson` writes.
+ * Only the indexes and offsets are kept in memory, games are read on demand.
+ */
+#[derive(Debug)]
+pub struct FileArchive {
+    path: PathBuf,
+    offsets: Vec<u64>,
+    index: ArchiveIndex,
+}
+
+impl FileArchive {
+    /// Opens or creates the archive and indexes all games in it.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(&path)?;
+        let mut archive = FileArchive {
+            path,
+            offsets: vec![],
+            index: ArchiveIndex::default(),
+        };
+        let mut reader = BufReader::new(file);
+        let mut offset = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let length = reader.read_line(&mut line)?;
+            if length == 0 {
+                break;
+            }
+            if !line.trim().is_empty() {
+                let game: GameFinishedInfo = serde_json::from_str(&line).map_err(|e| {
+                    Error::other(format!("game at byte {} is broken: {}", offset, e))
+                })?;
+                archive.index.add(&game);
+                archive.offsets.push(offset);
+            }
+            offset += length as u64;
+        }
+        Ok(archive)
+    }
+}
+
+impl GameArchive for FileArchive {
+    fn insert(&mut self, game: GameFinishedInfo) -> Result<GameId, Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut offset = file.seek(SeekFrom::End(0))?;
+        if offset > 0 {
+            // a last line without newline would be glued to the new game
+            let mut last = [0u8];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+                offset += 1;
+            }
+        }
+        let line = serde_json::to_string(&game).map_err(Error::other)?;
+        writeln!(file, "{}", line)?;
+        self.offsets.push(offset);
+        Ok(self.index.add(&game))
+    }
+
+    fn get(&self, id: GameId) -> Result<Option<GameFinishedInfo>, Error> {
+        let Some(offset) = self.offsets.get(id) else {
+            return Ok(None);
+        };
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(*offset))?;
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line)?;
+        serde_json::from_str(&line).map(Some).map_err(Error::other)
+    }
+
+    fn index(&self) -> &ArchiveIndex {
+        &self.index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use rand::prelude::IndexedRandom;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn helper_finished_games() -> Vec<GameFinishedInfo> {
+        let names = ["Alice", "Bob", "Carol", "Dave", "Eve"];
+        (0..40)
+            .map(|seed| {
+                let seats = [0, 1, 2, 3].map(|i| names[(i + seed as usize) % 5].to_string());
+                let mut game = Game::new_seeded(String::from("Archive"), seats, seed);
+                let mut rng = StdRng::seed_from_u64(seed);
+                while !game.ended() {
+                    let action = game.legal_actions().choose(&mut rng).unwrap().clone();
+                    game = game.apply_action(action).unwrap();
+                }
+                let mut finished = GameFinishedInfo::from(game);
+                let day = format!("2024-01-{:02} 20:00:00", seed % 28 + 1);
+                finished.set_times(day.clone(), day.clone(), day);
+                finished
+            })
+            .collect()
+    }
+
+    fn brute_force(games: &[GameFinishedInfo], query: &GameQuery) -> Vec<GameId> {
+        (0..games.len())
+            .filter(|id| IndexEntry::from_game(&games[*id]).matches(query))
+            .collect()
+    }
+
+    fn helper_queries() -> Vec<GameQuery> {
+        vec![
+            GameQuery::new(),
+            GameQuery::new()
+                .player("Alice")
+                .playing()
+                .min_value(200)
+                .lost(),
+            GameQuery::new().player("Bob").defending().won(),
+            GameQuery::new().player("Mallory"),
+            GameQuery::new().trump(Suit::Red),
+            GameQuery::new().min_value(150).max_value(300),
+            GameQuery::new()
+                .from_date("2024-01-05")
+                .to_date("2024-01-10"),
+            GameQuery::new().no_one_played(),
+            GameQuery::new().schwarz(true).lost(),
+        ]
+    }
+
+    #[test]
+    fn test_memory_archive_queries() {
+        let games = helper_finished_games();
+        let mut archive = MemoryArchive::new();
+        for game in games.clone() {
+            archive.insert(game).unwrap();
+        }
+        assert_eq!(archive.len(), games.len());
+        assert_eq!(archive.query(&GameQuery::new()).len(), games.len());
+        assert!(archive
+            .query(&GameQuery::new().player("Mallory"))
+            .is_empty());
+        for query in helper_queries() {
+            assert_eq!(archive.query(&query), brute_force(&games, &query));
+        }
+        for game in archive
+            .query_games(&GameQuery::new().player("Alice").playing())
+            .unwrap()
+        {
+            let seat = game.info.player_names.iter().position(|n| n == "Alice");
+            assert_eq!(
+                seat.unwrap() % 2,
+                game.playing_party.unwrap().0 as usize % 2
+            );
+        }
+    }
+
+    #[test]
+    fn test_file_archive_reopens() {
+        let path = std::env::temp_dir().join(format!("archive-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let games = helper_finished_games();
+        {
+            let mut archive = FileArchive::open(&path).unwrap();
+            for game in games.iter().take(25).cloned() {
+                archive.insert(game).unwrap();
+            }
+        }
+        let mut archive = FileArchive::open(&path).unwrap();
+        assert_eq!(archive.len(), 25);
+        for game in games.iter().skip(25).cloned() {
+            archive.insert(game).unwrap();
+        }
+        for query in helper_queries() {
+            assert_eq!(archive.query(&query), brute_force(&games, &query));
+        }
+        let loaded = archive.get(30).unwrap().unwrap();
+        assert_eq!(loaded.tricks.len(), games[30].tricks.len());
+        assert_eq!(
+            loaded.info.player_start_cards,
+            games[30].info.player_start_cards
+        );
+        assert!(archive.get(games.len()).unwrap().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_archive_appends_after_missing_newline() {
+        let path = std::env::temp_dir().join(format!("archive-nl-{}.jsonl", std::process::id()));
+        let games = helper_finished_games();
+        let line = serde_json::to_string(&games[0]).unwrap();
+        std::fs::write(&path, line).unwrap();
+        let mut archive = FileArchive::open(&path).unwrap();
+        assert_eq!(archive.insert(games[1].clone()).unwrap(), 1);
+        assert_eq!(
+            archive.get(1).unwrap().unwrap().info.player_names,
+            games[1].info.player_names
+        );
+        let archive = FileArchive::open(&path).unwrap();
+        assert_eq!(archive.len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
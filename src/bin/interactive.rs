@@ -2,6 +2,7 @@
 
 use std::io;
 
+use marjapussi::ai::pass_advisor::{rank_passes, PassAdvisorConfig};
 use marjapussi::game::gameinfo::GameFinishedInfo;
 use marjapussi::game::gamestate::GamePhase;
 use marjapussi::game::Game;
@@ -36,7 +37,7 @@ fn test_play() {
         println!("Cards 3: {:?}", game.state.players[3]);
         println!("actions:");
         for (i, action) in actions.iter().enumerate() {
-            println!("{}: {:?}", i, action);
+            println!("{}: {}", i, action);
             if i > 4 {
                 println!("...");
                 break;
@@ -44,19 +45,41 @@ fn test_play() {
         }
         if actions.len() > 4 {
             for (i, action) in actions.iter().rev().enumerate() {
-                println!("{}: {:?}", actions.len() - 1 - i, action);
+                println!("{}: {}", actions.len() - 1 - i, action);
                 if i > 4 {
                     println!("...");
                     break;
                 }
             }
         }
+        if matches!(
+            game.state.phase,
+            GamePhase::PassingForth | GamePhase::PassingBack
+        ) {
+            println!("suggested passes:");
+            for candidate in rank_passes(&game, &PassAdvisorConfig::default())
+                .iter()
+                .take(3)
+            {
+                println!("{} ({})", candidate.action, candidate.explanation());
+            }
+        }
         let mut user_input = String::new();
         let stdin = io::stdin(); // We get `Stdin` here.
         let _ = stdin.read_line(&mut user_input);
-        let action: i32 = user_input.trim().parse().unwrap_or(0);
+        // either the index of the action or the action in notation, e.g. "bid 140"
+        let action = match user_input.trim().parse::<usize>() {
+            Ok(index) => actions.get(index).cloned().unwrap_or(actions[0].clone()),
+            Err(_) => match game.action_from_notation(user_input.trim()) {
+                Ok(action) => action,
+                Err(err) => {
+                    println!("{}", err);
+                    continue;
+                }
+            },
+        };
         println!("chose: {}", action);
-        let res = game.apply_action(actions[action as usize].clone());
+        let res = game.apply_action(action);
         println!("results {:?}", res);
         game = res.ok().unwrap();
         actions = game.legal_actions();
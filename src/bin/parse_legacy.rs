@@ -1,48 +1,222 @@
-use std::fs::File;
-use std::io::{Read, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 
-use clap::{Arg, Command};
-use indicatif::ProgressIterator;
-use serde_json;
+use clap::{Arg, ArgAction, Command};
+use indicatif::ProgressBar;
+use serde::Serialize;
 
-use marjapussi::game::parse::parse_legacy_format;
-use marjapussi::game::parse::LegacyGameFormat;
+use marjapussi::game::parse::{
+    import_legacy_game, stream_legacy_games, validate_legacy_import, LegacyDiscrepancy,
+    LegacyImportError,
+};
+
+/// One line in the report file.
+#[derive(Serialize)]
+struct ReportEntry {
+    /// Position of the game in the input
+    index: usize,
+    #[serde(flatten)]
+    error: LegacyImportError,
+}
+
+/// One line in the validation file, only for games which disagree with the recorded outcome.
+#[derive(Serialize)]
+struct ValidationEntry {
+    index: usize,
+    id: String,
+    discrepancies: Vec<LegacyDiscrepancy>,
+}
 
 fn main() {
     let matches = Command::new("Legacy Game Format JSON Parser")
-        .version("0.1")
+        .version("0.2")
         .author("Samuel Leßmann")
-        .about("Parses a file in legacy JSON format with top level list.")
+        .about(
+            "Converts a file in legacy JSON format, either a top level list or \
+            newline-delimited, game by game. Failures are collected in a report.",
+        )
         .arg(
             Arg::new("filename")
                 .help("The JSON file to parse")
                 .required(true)
                 .index(1),
         )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Where to write converted games, defaults to new-<filename>"),
+        )
+        .arg(
+            Arg::new("report")
+                .short('r')
+                .long("report")
+                .help("Where to write failures, defaults to <output>.report.jsonl"),
+        )
+        .arg(
+            Arg::new("ndjson")
+                .long("ndjson")
+                .action(ArgAction::SetTrue)
+                .help("Write one game per line instead of a JSON list"),
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .action(ArgAction::SetTrue)
+                .requires("ndjson")
+                .help(
+                    "Continue an interrupted conversion, skips games already in output or report",
+                ),
+        )
+        .arg(
+            Arg::new("validate")
+                .long("validate")
+                .action(ArgAction::SetTrue)
+                .help("Compare every replay with the recorded outcome"),
+        )
+        .arg(
+            Arg::new("validation-output")
+                .long("validation-output")
+                .requires("validate")
+                .help("Where to write disagreements, defaults to <output>.discrepancies.jsonl"),
+        )
         .get_matches();
 
-    if let Some(filename) = matches.get_one::<String>("filename") {
-        let mut file = File::open(filename).expect("File not found");
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)
-            .expect("Error reading the file");
+    let filename = matches.get_one::<String>("filename").unwrap();
+    let output = matches
+        .get_one::<String>("output")
+        .cloned()
+        .unwrap_or_else(|| format!("new-{}", filename));
+    let report = matches
+        .get_one::<String>("report")
+        .cloned()
+        .unwrap_or_else(|| format!("{}.report.jsonl", output));
+    let ndjson = matches.get_flag("ndjson");
+    let resume = matches.get_flag("resume");
+    let validate = matches.get_flag("validate").then(|| {
+        matches
+            .get_one::<String>("validation-output")
+            .cloned()
+            .unwrap_or_else(|| format!("{}.discrepancies.jsonl", output))
+    });
 
-        let new_format_json = to_new_format(contents);
+    let input = BufReader::new(File::open(filename).expect("File not found"));
+    let (output_file, already_converted) = open_resumable(&output, resume);
+    let (report_file, already_failed) = open_resumable(&report, resume);
+    let skip = already_converted + already_failed;
+    let mut output_file = BufWriter::new(output_file);
+    let mut report_file = BufWriter::new(report_file);
+    let mut validation_file = validate
+        .as_ref()
+        .map(|path| BufWriter::new(open_resumable(path, resume).0));
 
-        // save to file
-        let new_filename = format!("new-{}", filename);
-        let mut new_file = File::create(new_filename).expect("Error creating the new file");
-        new_file
-            .write_all(new_format_json.as_bytes())
+    if !ndjson {
+        output_file
+            .write_all(b"[\n")
             .expect("Error writing the new file");
     }
+
+    let progress = ProgressBar::new_spinner();
+    let (mut converted, mut failed, mut disagreeing) = (0, 0, 0);
+    stream_legacy_games(input, |index, game| {
+        progress.inc(1);
+        if index < skip {
+            return;
+        }
+        let legacy = game.as_ref().ok().filter(|_| validate.is_some()).cloned();
+        match game.and_then(import_legacy_game) {
+            Ok(finished) => {
+                if let (Some(legacy), Some(file)) = (legacy, validation_file.as_mut()) {
+                    let discrepancies = validate_legacy_import(&legacy, &finished);
+                    if !discrepancies.is_empty() {
+                        let entry = ValidationEntry {
+                            index,
+                            id: legacy.id().to_string(),
+                            discrepancies,
+                        };
+                        let entry = serde_json::to_string(&entry)
+                            .expect("Error serializing the discrepancies");
+                        writeln!(file, "{}", entry).expect("Error writing the discrepancies");
+                        file.flush().expect("Error writing the discrepancies");
+                        disagreeing += 1;
+                    }
+                }
+                let json = match ndjson {
+                    true => serde_json::to_string(&finished),
+                    false => serde_json::to_string_pretty(&finished),
+                }
+                .expect("Error serializing the new format");
+                if !ndjson && converted > 0 {
+                    output_file
+                        .write_all(b",\n")
+                        .expect("Error writing the new file");
+                }
+                output_file
+                    .write_all(json.as_bytes())
+                    .expect("Error writing the new file");
+                if ndjson {
+                    output_file
+                        .write_all(b"\n")
+                        .expect("Error writing the new file");
+                }
+                output_file.flush().expect("Error writing the new file");
+                converted += 1;
+            }
+            Err(error) => {
+                let entry = serde_json::to_string(&ReportEntry { index, error })
+                    .expect("Error serializing the report");
+                writeln!(report_file, "{}", entry).expect("Error writing the report");
+                report_file.flush().expect("Error writing the report");
+                failed += 1;
+            }
+        }
+    })
+    .expect("Error reading the file");
+    progress.finish();
+
+    if !ndjson {
+        output_file
+            .write_all(b"\n]")
+            .expect("Error writing the new file");
+    }
+    output_file.flush().expect("Error writing the new file");
+    println!(
+        "converted {} games, {} failed (see {}), skipped {}",
+        converted, failed, report, skip
+    );
+    if let Some(path) = validate {
+        println!(
+            "{} converted games disagree with the recorded outcome (see {})",
+            disagreeing, path
+        );
+    }
 }
 
-fn to_new_format(contents: String) -> String {
-    let game_data: Vec<LegacyGameFormat> = serde_json::from_str(contents.as_str()).unwrap();
-    let mut converted = vec![];
-    for game in game_data.into_iter().progress() {
-        converted.push(parse_legacy_format(game).unwrap());
+/**
+ * Opens a line based file for writing.
+ * When resuming, the file is appended to and the number of complete lines is returned,
+ * a partially written last line is cut off.
+ */
+fn open_resumable(path: &str, resume: bool) -> (File, usize) {
+    if !resume {
+        return (File::create(path).expect("Error creating file"), 0);
     }
-    serde_json::to_string_pretty(&converted).expect("Error serializing the new format")
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .expect("Error opening file");
+    let mut contents = vec![];
+    file.read_to_end(&mut contents).expect("Error reading file");
+    let complete = contents
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |last| last + 1);
+    file.set_len(complete as u64)
+        .expect("Error truncating file");
+    file.seek(SeekFrom::End(0)).expect("Error seeking file");
+    let lines = contents[..complete].lines().count();
+    (file, lines)
 }
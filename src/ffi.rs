@@ -0,0 +1,326 @@
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+use crate::game::gameevent::GameAction;
+use crate::game::gameinfo::GameInfoPlayer;
+use crate::game::player::PlaceAtTable;
+use crate::game::Game;
+
+/*
+C ABI for embedding the engine, see `ffi/marjapussi.h` which is generated by cbindgen.
+Games are opaque handles owned by the caller. Functions writing text follow snprintf:
+they return the length without the terminating zero and write at most `capacity` bytes.
+ */
+
+pub const MP_OK: i32 = 0;
+pub const MP_ERR_NULL: i32 = -1;
+pub const MP_ERR_INDEX: i32 = -2;
+pub const MP_ERR_ILLEGAL: i32 = -3;
+
+/// Snapshot of everything instead of a seat's view.
+pub const MP_ALL_SEATS: i32 = -1;
+
+/// Opaque game handle.
+pub struct MpGame {
+    game: Game,
+    /// Legal actions in the order they are enumerated
+    legal: Vec<GameAction>,
+}
+
+impl MpGame {
+    fn new(game: Game) -> Self {
+        MpGame {
+            legal: game.legal_actions(),
+            game,
+        }
+    }
+}
+
+fn write_str(text: &str, buffer: *mut c_char, capacity: usize) -> usize {
+    if !buffer.is_null() && capacity > 0 {
+        let len = text.len().min(capacity - 1);
+        // Safety: the caller provides `capacity` writable bytes at `buffer`
+        unsafe {
+            ptr::copy_nonoverlapping(text.as_ptr(), buffer as *mut u8, len);
+            *buffer.add(len) = 0;
+        }
+    }
+    text.len()
+}
+
+/// Safety: `text` is null or a zero terminated string.
+unsafe fn read_str(text: *const c_char) -> Option<String> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok().map(String::from)
+}
+
+fn snapshot(game: &Game) -> serde_json::Value {
+    serde_json::json!({
+        "info": game.info,
+        "events": game.all_events,
+        "phase": format!("{:?}", game.state.phase),
+        "value": game.state.value.0,
+        "player_at_turn": game.state.player_at_turn.0,
+        "hands": game.state.players.iter()
+            .map(|player| player.cards.iter().map(|card| card.to_string()).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        "legal_actions": game.legal.iter()
+            .map(|action| action.to_string())
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Creates a game dealt from the seed, free it with `mp_game_free`.
+/// `name` and `players` (four names) may be null for defaults.
+/// Returns null if a name is no valid UTF-8.
+///
+/// # Safety
+/// `name` is null or a zero terminated string, `players` is null or points to four of them.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_new(
+    name: *const c_char,
+    players: *const *const c_char,
+    seed: u64,
+) -> *mut MpGame {
+    let name = match name.is_null() {
+        true => String::from("FFI"),
+        false => match read_str(name) {
+            Some(name) => name,
+            None => return ptr::null_mut(),
+        },
+    };
+    let mut names = ["Seat 0", "Seat 1", "Seat 2", "Seat 3"].map(String::from);
+    if !players.is_null() {
+        for (i, player) in names.iter_mut().enumerate() {
+            match read_str(*players.add(i)) {
+                Some(name) => *player = name,
+                None => return ptr::null_mut(),
+            }
+        }
+    }
+    Box::into_raw(Box::new(MpGame::new(Game::new_seeded(name, names, seed))))
+}
+
+/// # Safety
+/// `game` is null or was returned by this library and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_free(game: *mut MpGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// A copy of the game which has to be freed separately.
+///
+/// # Safety
+/// `game` is null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_clone(game: *const MpGame) -> *mut MpGame {
+    match game.as_ref() {
+        Some(game) => Box::into_raw(Box::new(MpGame::new(game.game.clone()))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Number of legal actions, 0 once the game ended.
+///
+/// # Safety
+/// `game` is null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_legal_count(game: *const MpGame) -> usize {
+    game.as_ref().map_or(0, |game| game.legal.len())
+}
+
+/// Writes the seat of every legal action into `seats` and the index of the action
+/// for `ai::encoding::action_index` into `indices`, either may be null.
+/// Returns the number of legal actions, at most `capacity` entries are written.
+///
+/// # Safety
+/// `game` is null or a live handle, the buffers are null or hold `capacity` entries.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_legal_actions(
+    game: *const MpGame,
+    seats: *mut u8,
+    indices: *mut u32,
+    capacity: usize,
+) -> usize {
+    let Some(game) = game.as_ref() else {
+        return 0;
+    };
+    for (i, action) in game.legal.iter().take(capacity).enumerate() {
+        if !seats.is_null() {
+            *seats.add(i) = action.player.0;
+        }
+        if !indices.is_null() {
+            *indices.add(i) = crate::ai::encoding::action_index(&action.action_type) as u32;
+        }
+    }
+    game.legal.len()
+}
+
+/// Writes the legal action at `index` in notation, e.g. `2 bid 140`.
+///
+/// # Safety
+/// `game` is null or a live handle, `buffer` is null or holds `capacity` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_legal_action_notation(
+    game: *const MpGame,
+    index: usize,
+    buffer: *mut c_char,
+    capacity: usize,
+) -> usize {
+    match game.as_ref().and_then(|game| game.legal.get(index)) {
+        Some(action) => write_str(&action.to_string(), buffer, capacity),
+        None => write_str("", buffer, capacity),
+    }
+}
+
+/// Applies the legal action at `index` of the enumeration in place.
+///
+/// # Safety
+/// `game` is null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_apply_index(game: *mut MpGame, index: usize) -> i32 {
+    let Some(game) = game.as_mut() else {
+        return MP_ERR_NULL;
+    };
+    let Some(action) = game.legal.get(index).cloned() else {
+        return MP_ERR_INDEX;
+    };
+    match game.game.apply_action(action) {
+        Ok(next) => {
+            *game = MpGame::new(next);
+            MP_OK
+        }
+        Err(_) => MP_ERR_ILLEGAL,
+    }
+}
+
+/// Applies a `;` separated move list in place, all or nothing.
+///
+/// # Safety
+/// `game` is null or a live handle, `moves` is null or a zero terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_apply_notation(game: *mut MpGame, moves: *const c_char) -> i32 {
+    let (Some(game), Some(moves)) = (game.as_mut(), read_str(moves)) else {
+        return MP_ERR_NULL;
+    };
+    match game.game.apply_notation(&moves) {
+        Ok(next) => {
+            *game = MpGame::new(next);
+            MP_OK
+        }
+        Err(_) => MP_ERR_ILLEGAL,
+    }
+}
+
+/// # Safety
+/// `game` is null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_ended(game: *const MpGame) -> bool {
+    game.as_ref().is_some_and(|game| game.game.ended())
+}
+
+/// Seat at turn, -1 for null.
+///
+/// # Safety
+/// `game` is null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_player_at_turn(game: *const MpGame) -> i32 {
+    game.as_ref()
+        .map_or(MP_ERR_NULL, |game| game.game.state.player_at_turn.0 as i32)
+}
+
+/// Writes the game as JSON: a seat's view for seats 0 to 3,
+/// or with `MP_ALL_SEATS` all hands and events. Returns 0 for null or an invalid seat.
+///
+/// # Safety
+/// `game` is null or a live handle, `buffer` is null or holds `capacity` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mp_game_snapshot_json(
+    game: *const MpGame,
+    seat: i32,
+    buffer: *mut c_char,
+    capacity: usize,
+) -> usize {
+    let json = match (game.as_ref(), seat) {
+        (Some(game), MP_ALL_SEATS) => snapshot(&game.game),
+        (Some(game), 0..=3) => {
+            GameInfoPlayer::from_game(game.game.clone(), PlaceAtTable(seat as u8)).to_json()
+        }
+        _ => return write_str("", buffer, capacity),
+    };
+    write_str(&json.to_string(), buffer, capacity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi_game() {
+        unsafe {
+            let game = mp_game_new(ptr::null(), ptr::null(), 5);
+            assert_eq!(mp_game_legal_count(game), 4);
+            assert_eq!(mp_game_apply_index(game, 4), MP_ERR_INDEX);
+            assert_eq!(
+                mp_game_apply_notation(game, c"start; start; start; start".as_ptr()),
+                MP_OK
+            );
+            assert_eq!(
+                mp_game_apply_notation(game, c"bid 500".as_ptr()),
+                MP_ERR_ILLEGAL
+            );
+
+            let count = mp_game_legal_count(game);
+            let mut seats = vec![9; count];
+            let mut indices = vec![0; count];
+            let written =
+                mp_game_legal_actions(game, seats.as_mut_ptr(), indices.as_mut_ptr(), count);
+            assert_eq!(written, count);
+            assert!(seats.iter().all(|seat| *seat == 0));
+            assert_eq!(&indices[..2], &[1, 2]);
+
+            let mut buffer = [0 as c_char; 4];
+            let len = mp_game_legal_action_notation(game, 1, buffer.as_mut_ptr(), buffer.len());
+            assert_eq!(len, "0 bid 120".len());
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(), "0 b");
+
+            let copy = mp_game_clone(game);
+            while !mp_game_ended(game) {
+                assert_eq!(mp_game_apply_index(game, 0), MP_OK);
+            }
+            assert_eq!(mp_game_legal_count(game), 0);
+            assert!(!mp_game_ended(copy));
+
+            let len = mp_game_snapshot_json(copy, 0, ptr::null_mut(), 0);
+            let mut buffer = vec![0 as c_char; len + 1];
+            mp_game_snapshot_json(copy, 0, buffer.as_mut_ptr(), buffer.len());
+            let view: serde_json::Value =
+                serde_json::from_str(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap()).unwrap();
+            assert_eq!(view["own_cards"].as_array().unwrap().len(), 9);
+            assert_eq!(mp_game_snapshot_json(copy, 4, ptr::null_mut(), 0), 0);
+            assert!(mp_game_snapshot_json(copy, MP_ALL_SEATS, ptr::null_mut(), 0) > len);
+
+            mp_game_free(copy);
+            mp_game_free(game);
+        }
+    }
+
+    /// Set `UPDATE_FFI_HEADER=1` to replace `ffi/marjapussi.h` with the generated header.
+    #[test]
+    fn test_header_is_up_to_date() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/marjapussi.h"));
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/ffi/marjapussi.h");
+        if std::env::var_os("UPDATE_FFI_HEADER").is_some() {
+            std::fs::write(path, generated).unwrap();
+        }
+        let committed = std::fs::read_to_string(path).unwrap();
+        assert!(
+            committed == generated,
+            "ffi/marjapussi.h is outdated, run the tests with UPDATE_FFI_HEADER=1"
+        );
+    }
+}
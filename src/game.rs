@@ -1,34 +1,38 @@
-use std::time::SystemTime;
-
-use chrono::offset::Local;
-use chrono::DateTime;
-
 use crate::game::errors::GameError;
-use crate::game::gameevent::{ActionType, GameAction, GameEvent};
+use crate::game::gameevent::{GameAction, GameEvent};
 use crate::game::gamestate::GamePhase;
-use crate::game::player::create_players;
+use crate::game::legal_actions::LegalActions;
+use crate::game::player::{create_players, seeded_cards, PlaceAtTable};
 use crate::game::points::Points;
+use crate::game::zobrist::update_fingerprint;
 
 use self::{cards::Card, gameinfo::GameMetaInfo, gamestate::GameState};
 
 mod apply_action;
 pub mod cards;
+pub mod deal;
 pub mod errors;
 pub mod gameevent;
 pub mod gameinfo;
 pub mod gamestate;
 pub mod legal_actions;
+pub mod notation;
 pub mod parse;
 pub mod player;
 pub mod points;
+pub mod record;
+pub mod search;
 pub mod series;
+pub mod spectator;
+pub mod zobrist;
 
 /// Wrapper for Game and all of its details.
 #[derive(Debug, Clone)]
 pub struct Game {
     pub info: GameMetaInfo,
     pub state: GameState,
-    pub legal_actions: Vec<GameAction>,
+    /// The legal actions in compact form, `legal_actions()` lists all of them.
+    pub legal: LegalActions,
     pub last_state: Option<GameState>,
     pub all_events: Vec<GameEvent>,
 }
@@ -40,17 +44,29 @@ impl Game {
         let mut game = Game {
             info: GameMetaInfo::create(name, player_names, players.clone()),
             state: GameState::create(players.clone()),
-            legal_actions: vec![],
+            legal: LegalActions::empty(PlaceAtTable(0)),
             last_state: None,
             all_events: vec![],
         };
-        game.legal_actions = game.legal_actions();
+        game.legal = game.compact_legal_actions();
+        game
+    }
+
+    /// Creates a game with cards dealt from the seed, see `player::seeded_cards`.
+    pub fn new_seeded(name: String, player_names: [String; 4], seed: u64) -> Self {
+        let mut game = Game::new(name, player_names, Some(seeded_cards(seed)));
+        game.info.seed = Some(seed);
         game
     }
 
     /// Creates list with all legal actions in the current state of the game.
     pub fn legal_actions(&self) -> Vec<GameAction> {
-        let mut legal = self.state.phase.legal_actions(self);
+        self.legal.to_vec()
+    }
+
+    /// All legal actions in the current state of the game, without listing every bid and pass.
+    pub fn compact_legal_actions(&self) -> LegalActions {
+        let mut legal = self.state.phase.compact_legal_actions(self);
         let disallow_undo: Vec<GamePhase> = vec![
             GamePhase::WaitingForStart,
             GamePhase::Ended,
@@ -63,20 +79,15 @@ impl Game {
         // disallow undo special cases
         match self.state.phase.clone() {
             GamePhase::PendingUndo(_) => {
-                return legal.clone();
+                return legal;
             }
-            GamePhase::Bidding => {
-                if self.state.value == Points(115) {
-                    return legal.clone();
-                }
+            GamePhase::Bidding if self.state.value == Points(115) => {
+                return legal;
             }
             _ => {}
         }
-        if self.last_state.is_some() {
-            legal.push(GameAction {
-                action_type: ActionType::UndoRequest,
-                player: self.last_state.clone().unwrap().player_at_turn.clone(),
-            });
+        if let Some(last_state) = &self.last_state {
+            legal.undo_request = Some(last_state.player_at_turn.clone());
         }
         legal
     }
@@ -84,11 +95,12 @@ impl Game {
     /// Tries creating a new Game with state after applying a given action.
     /// Can fail and does not mutate the existing Game.
     pub fn apply_action(&self, action: GameAction) -> Result<Game, GameError> {
-        if !self.legal_actions.contains(&action) {
+        if !self.legal.is_legal(&action) {
             return Err(GameError::IllegalAction);
         }
-        let (next_game_meta, next_game_state, this_callback, last_state) =
+        let (next_game_meta, mut next_game_state, this_callback, last_state) =
             action.clone().action_type.apply_action(&action, self);
+        update_fingerprint(&self.state, &mut next_game_state);
 
         // next game Object
         let this_event = GameEvent {
@@ -102,11 +114,11 @@ impl Game {
         let mut next_game = Game {
             info: next_game_meta,
             state: next_game_state,
-            legal_actions: vec![],
+            legal: LegalActions::empty(PlaceAtTable(0)),
             last_state,
             all_events: next_all_events,
         };
-        next_game.legal_actions = next_game.legal_actions();
+        next_game.legal = next_game.compact_legal_actions();
         Ok(next_game)
     }
 
@@ -125,17 +137,19 @@ impl Game {
     }
 }
 
+/// Local time, empty on wasm32 where there is no system clock.
 pub fn current_time_string() -> String {
-    let system_time = SystemTime::now();
-    let datetime: DateTime<Local> = system_time.into();
-    format!("{}", datetime.format("%Y-%m-%d %T"))
+    #[cfg(not(target_arch = "wasm32"))]
+    return format!("{}", chrono::Local::now().format("%Y-%m-%d %T"));
+    #[cfg(target_arch = "wasm32")]
+    return String::new();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::game::cards::Suit;
-    use crate::game::gameevent::QuestionType;
+    use crate::game::gameevent::{effective_events, ActionType, QuestionType};
     use crate::game::player::{PlaceAtTable, PlayerTrumpPossibilities};
     use rand::prelude::IndexedMutRandom;
 
@@ -170,13 +184,13 @@ mod tests {
     #[test]
     fn test_starting() {
         let mut game = helper_create_game();
-        let mut actions = game.legal_actions.clone();
+        let mut actions = game.legal_actions();
         assert_eq!(actions.len(), 4);
         for _ in 0..4 {
             let act = actions.pop().unwrap();
             let res = game.apply_action(act);
             game = res.ok().unwrap();
-            actions = game.legal_actions.clone();
+            actions = game.legal_actions();
         }
         assert_eq!(game.state.phase, GamePhase::Bidding);
     }
@@ -188,10 +202,10 @@ mod tests {
         assert_eq!(actions.len(), 4);
         for _ in 0..4 {
             game = game.apply_action(actions.pop().unwrap()).ok().unwrap();
-            actions.clone_from(&game.legal_actions);
+            actions.clone_from(&game.legal_actions());
         }
         assert_eq!(game.state.phase, GamePhase::Bidding);
-        actions.clone_from(&game.legal_actions);
+        actions.clone_from(&game.legal_actions());
         assert_eq!(actions.len(), 62);
         let bid140 = GameAction {
             action_type: ActionType::NewBid(140),
@@ -202,12 +216,12 @@ mod tests {
         let res = game.apply_action(forbidden_action);
         assert!(res.is_err());
         for _ in 0..4 {
-            actions = game.legal_actions.clone();
+            actions = game.legal_actions();
             let res = game.apply_action(actions[3].clone());
             game = res.ok().unwrap();
         }
         for _ in 0..3 {
-            actions = game.legal_actions.clone();
+            actions = game.legal_actions();
             assert_eq!(game.state.phase, GamePhase::Bidding);
             let res = game.apply_action(actions[0].clone());
             game = res.ok().unwrap();
@@ -218,8 +232,8 @@ mod tests {
         assert_eq!(game.state.player_at_turn().name, String::from("S3"));
         assert_eq!(game.state.phase, GamePhase::PassingForth);
         assert_eq!(game.state.player_at_turn().cards.len(), 9);
-        assert_eq!(game.legal_actions.len(), 127); //nCr(9,4) + 1
-        actions = game.legal_actions.clone();
+        assert_eq!(game.legal.count(), 127); //nCr(9,4) + 1
+        actions = game.legal_actions();
         let res = game.apply_action(actions[2].clone());
         game = res.ok().unwrap();
         assert_eq!(game.state.player_at_turn().cards.len(), 13);
@@ -233,7 +247,7 @@ mod tests {
         //passing back
         assert_eq!(game.state.player_at_turn().name, String::from("S1"));
         assert_eq!(game.state.phase, GamePhase::PassingBack);
-        actions.clone_from(&game.legal_actions);
+        actions.clone_from(&game.legal_actions());
         let res = game.apply_action(actions[9].clone());
         game = res.ok().unwrap();
         assert_eq!(game.state.player_at_turn().cards.len(), 9);
@@ -245,14 +259,30 @@ mod tests {
             9
         );
         assert_eq!(game.state.phase, GamePhase::Raising);
-        actions.clone_from(&game.legal_actions);
+        actions.clone_from(&game.legal_actions());
         let res = game.apply_action(actions[2].clone());
         game = res.ok().unwrap();
         assert_eq!(game.state.phase, GamePhase::Trick);
-        actions.clone_from(&game.legal_actions);
+        actions.clone_from(&game.legal_actions());
         let _res = game.apply_action(actions[0].clone());
     }
 
+    #[test]
+    fn test_effective_events_drop_undone_actions() {
+        let game = helper_create_game()
+            .apply_notation("start; start; start; start; bid 120; undo; undo accept")
+            .unwrap();
+        assert!(matches!(game.state.phase, GamePhase::PendingUndo(_)));
+        let game = game.apply_notation("undo accept; bid 125").unwrap();
+        assert_eq!(game.state.value, Points(125));
+        let effective = effective_events(&game.all_events);
+        assert_eq!(effective.len(), 5);
+        assert_eq!(
+            effective.last().unwrap().last_action.action_type,
+            ActionType::NewBid(125)
+        );
+    }
+
     #[test]
     fn test_random_game_multi() {
         for _ in 0..100 {
@@ -320,13 +350,13 @@ mod tests {
         game.state.started = true;
         game.state.phase = GamePhase::StartTrick;
         game.state.player_at_turn = p0.clone();
-        game.legal_actions = game.legal_actions();
+        game.legal = game.compact_legal_actions();
 
-        assert!(game.legal_actions.contains(&GameAction {
+        assert!(game.legal.is_legal(&GameAction {
             action_type: ActionType::AnnounceTrump(Suit::Red),
             player: p0.clone(),
         }));
-        assert!(game.legal_actions.contains(&GameAction {
+        assert!(game.legal.is_legal(&GameAction {
             action_type: ActionType::Question(QuestionType::Yours),
             player: p0.clone(),
         }));
@@ -344,12 +374,12 @@ mod tests {
 
         game.state.phase = GamePhase::StartTrick;
         game.state.player_at_turn = p0.clone();
-        game.legal_actions = game.legal_actions();
-        assert!(game.legal_actions.contains(&GameAction {
+        game.legal = game.compact_legal_actions();
+        assert!(game.legal.is_legal(&GameAction {
             action_type: ActionType::Question(QuestionType::Yours),
             player: p0.clone(),
         }));
-        assert!(!game.legal_actions.contains(&GameAction {
+        assert!(!game.legal.is_legal(&GameAction {
             action_type: ActionType::AnnounceTrump(Suit::Red),
             player: p0.clone(),
         }));
@@ -367,12 +397,12 @@ mod tests {
 
         game.state.phase = GamePhase::StartTrick;
         game.state.player_at_turn = p0.clone();
-        game.legal_actions = game.legal_actions();
-        assert!(!game.legal_actions.contains(&GameAction {
+        game.legal = game.compact_legal_actions();
+        assert!(!game.legal.is_legal(&GameAction {
             action_type: ActionType::Question(QuestionType::Yours),
             player: p0.clone(),
         }));
-        assert!(game.legal_actions.contains(&GameAction {
+        assert!(game.legal.is_legal(&GameAction {
             action_type: ActionType::Question(QuestionType::YourHalf(Suit::Red)),
             player: p0,
         }));
@@ -380,11 +410,11 @@ mod tests {
 
     pub fn test_random_game_random() {
         let mut game = helper_create_game();
-        let mut actions = game.legal_actions.clone();
+        let mut actions = game.legal_actions();
         assert_eq!(actions.len(), 4);
         for _ in 0..4 {
             game = game.apply_action(actions.pop().unwrap()).ok().unwrap();
-            actions.clone_from(&game.legal_actions);
+            actions.clone_from(&game.legal_actions());
         }
         let mut i = 0;
         while game.state.phase != GamePhase::Ended {
@@ -392,7 +422,7 @@ mod tests {
             if i >= 200 {
                 panic!("Too many moves! Game: {:#?}\n ", game,);
             }
-            actions.clone_from(&game.legal_actions);
+            actions.clone_from(&game.legal_actions());
             let opt_select = actions.choose_mut(&mut rand::rng());
             if opt_select.is_none() {
                 panic!("Game: {:#?}\n Action: {:?}", game, opt_select);
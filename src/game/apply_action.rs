@@ -1,9 +1,10 @@
-use crate::game::cards::{high_card, Card};
+use crate::game::cards::Card;
 use crate::game::gameevent::{ActionType, AnswerType, GameAction, GameCallback, QuestionType};
 use crate::game::gameinfo::GameMetaInfo;
 use crate::game::gamestate::{FinishedTrick, GamePhase, GameState};
 use crate::game::player::PlayerTrumpPossibilities;
-use crate::game::points::{points_trick, Points};
+use crate::game::points::Points;
+use crate::game::search::{card_index, trick_points, trick_winner};
 use crate::game::{cards, current_time_string, Game};
 
 impl ActionType {
@@ -231,30 +232,24 @@ pub fn act_card(card: Card, next_game_state: &mut GameState) {
     next_game_state.phase = GamePhase::Trick;
     next_game_state.player_at_turn = next_game_state.player_at_turn.next();
     if next_game_state.current_trick.len() == 4 {
-        //determine next player
-        let mut trick: Vec<&Card> = vec![];
-        for c in &next_game_state.current_trick {
-            trick.push(c);
-        }
-        let high_card = high_card(trick.clone(), next_game_state.trump).unwrap();
-        for card in trick {
-            if card == high_card {
-                break;
-            }
+        //determine next player, same rules as the search state
+        let trick: Vec<u8> = next_game_state
+            .current_trick
+            .iter()
+            .map(card_index)
+            .collect();
+        for _ in 0..trick_winner(&trick, next_game_state.trump) {
             next_game_state.player_at_turn = next_game_state.player_at_turn.next();
         }
         next_game_state.phase = GamePhase::StartTrick;
         // save trick
         let cards_in_last_trick: [Card; 4] =
             next_game_state.current_trick.clone().try_into().unwrap();
-        let mut trick_points = points_trick(cards_in_last_trick.to_vec());
-        if next_game_state.all_tricks.len() == 8 {
-            trick_points += Points(20);
-        }
+        let last = next_game_state.all_tricks.len() == 8;
         next_game_state.all_tricks.push(FinishedTrick {
             cards: cards_in_last_trick.clone(),
             winner: next_game_state.player_at_turn.clone(),
-            points: trick_points,
+            points: Points(trick_points(&trick, last)),
         });
     }
 }
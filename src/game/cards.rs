@@ -3,22 +3,25 @@
 use std::cmp::max;
 use std::fmt;
 
-use itertools::concat;
 use serde::{Deserialize, Serialize};
 use serde_with::{DeserializeFromStr, SerializeDisplay};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+use crate::game::notation::{suit_str, value_str};
 use crate::game::parse::parse_card;
+use crate::game::search::{allowed_mask, card_index, cards_mask};
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, DeserializeFromStr, SerializeDisplay)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, DeserializeFromStr, SerializeDisplay)]
 pub struct Card {
     /// Only compare cards with same color
     pub suit: Suit,
     pub value: Value,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, EnumIter, Serialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, EnumIter, Serialize, Deserialize,
+)]
 pub enum Suit {
     Green,
     Acorns,
@@ -32,7 +35,9 @@ impl fmt::Display for Suit {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, EnumIter, Serialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, EnumIter, Serialize, Deserialize,
+)]
 pub enum Value {
     Six,
     Seven,
@@ -105,100 +110,24 @@ pub fn high_card(trick: Vec<&Card>, trump: Option<Suit>) -> Option<&Card> {
 /**
  * Only for the first played card in the game. Proper play in rest of first trick handled elsewhere.
  */
+/// Cards which may lead the first trick.
 pub fn allowed_first(cards: Vec<&Card>) -> Vec<&Card> {
-    let mut allowed = cards
-        .clone()
-        .into_iter()
-        .filter(|c| c.value == Value::Ace)
-        .collect::<Vec<_>>();
-    if allowed.is_empty() {
-        allowed = cards
-            .clone()
-            .into_iter()
-            .filter(|c| c.suit == Suit::Green)
-            .collect::<Vec<_>>();
-    }
-    if allowed.is_empty() {
-        cards
-    } else {
-        allowed
-    }
+    allowed_cards(vec![], cards, None, true)
 }
 
-/**
- * Returns general high card.
- */
+/// Cards of the hand which may be played on the trick, the rules are in `search::allowed_mask`.
 pub fn allowed_cards<'a>(
     trick: Vec<&'a Card>,
     cards: Vec<&'a Card>,
     trump: Option<Suit>,
     first_trick: bool,
 ) -> Vec<&'a Card> {
-    let current_high_card = high_card(trick.clone(), trump);
-    /*println!(
-        "trick={:?} wiht cards={:?} and trump={:?}",
-        trick, cards, trump
-    );*/
-    match current_high_card {
-        Some(current_high_card) => {
-            let trick_suit = trick[0].suit;
-            if first_trick {
-                let first_trick_ace: Vec<&Card> = cards
-                    .clone()
-                    .into_iter()
-                    .filter(|c| c.suit == trick_suit && c.value == Value::Ace)
-                    .collect();
-                if first_trick_ace.len() == 1 {
-                    return first_trick_ace;
-                }
-            }
-            let same_color_cards: Vec<&Card> = cards
-                .clone()
-                .into_iter()
-                .filter(|c: &&Card| c.suit == trick_suit)
-                .collect();
-            let higher_cards: Vec<&Card> = cards
-                .clone()
-                .into_iter()
-                .filter(|c| {
-                    high_card(concat([trick.clone(), vec![c]]), trump) > Some(current_high_card)
-                })
-                .collect();
-            let higher_same_color = higher_cards
-                .clone()
-                .into_iter()
-                .filter(|c| c.suit == trick_suit)
-                .collect::<Vec<_>>();
-            if !higher_same_color.is_empty() {
-                return higher_same_color;
-            }
-            if !same_color_cards.is_empty() {
-                return same_color_cards;
-            }
-            if !higher_cards.is_empty() {
-                return higher_cards;
-            }
-            if let Some(trump_suit) = trump {
-                let trump_cards: Vec<&Card> = cards
-                    .clone()
-                    .into_iter()
-                    .filter(|c| c.suit == trump_suit)
-                    .collect();
-                if !trump_cards.is_empty() {
-                    return trump_cards;
-                }
-            }
-            cards
-        }
-        None => {
-            //the trick has to be empty
-            if first_trick {
-                allowed_first(cards)
-            } else {
-                cards
-            }
-        }
-    }
+    let trick: Vec<u8> = trick.into_iter().map(card_index).collect();
+    let allowed = allowed_mask(cards_mask(cards.clone()), &trick, trump, first_trick);
+    cards
+        .into_iter()
+        .filter(|card| allowed & 1 << card_index(card) != 0)
+        .collect()
 }
 
 pub fn halves(cards: Vec<Card>) -> Vec<Suit> {
@@ -247,24 +176,7 @@ pub fn print_cards(cards: &[Card]) {
 
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let suit = match self.suit {
-            Suit::Red => "r",
-            Suit::Bells => "s",
-            Suit::Acorns => "e",
-            Suit::Green => "g",
-        };
-        let value = match self.value {
-            Value::Ace => "A",
-            Value::Ten => "Z",
-            Value::King => "K",
-            Value::Ober => "O",
-            Value::Unter => "U",
-            Value::Nine => "9",
-            Value::Eight => "8",
-            Value::Seven => "7",
-            Value::Six => "6",
-        };
-        write!(f, "{}-{}", suit, value)
+        write!(f, "{}-{}", suit_str(self.suit), value_str(self.value))
     }
 }
 
@@ -0,0 +1,436 @@
+use std::fmt;
+use std::io::Error;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use strum::IntoEnumIterator;
+
+use crate::game::cards::{get_all_cards, pairs, Card, Suit, Value};
+use crate::game::notation::{parse_suit_str, suit_str, value_str};
+use crate::game::player::PlaceAtTable;
+
+/// Deals tried before giving up on constraints which are too rare or impossible.
+pub const MAX_ATTEMPTS: usize = 100_000;
+
+/// A seat or a party, written `2` or `0+2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Holder {
+    Seat(PlaceAtTable),
+    /// Both seats of the party, given by its seat 0 or 1
+    Party(PlaceAtTable),
+}
+
+impl Holder {
+    fn hands<'a>(&self, deal: &'a [Vec<Card>; 4]) -> Vec<&'a Vec<Card>> {
+        match self {
+            Holder::Seat(seat) => vec![&deal[seat.0 as usize]],
+            Holder::Party(party) => vec![&deal[party.0 as usize], &deal[party.0 as usize + 2]],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Count {
+    Cards,
+    Suit(Suit),
+    Value(Value),
+    /// Suits of which one hand holds king and ober
+    Pairs,
+    /// Suits of which one hand holds exactly one of king and ober.
+    /// Unlike `cards::halves` a suit with the full pair is not counted.
+    Halves,
+}
+
+impl Count {
+    fn of_hand(&self, hand: &[Card]) -> usize {
+        let has = |suit: Suit, value: Value| hand.contains(&Card { suit, value });
+        match self {
+            Count::Cards => hand.len(),
+            Count::Suit(suit) => hand.iter().filter(|card| card.suit == *suit).count(),
+            Count::Value(value) => hand.iter().filter(|card| card.value == *value).count(),
+            Count::Pairs => pairs(hand.to_vec()).len(),
+            Count::Halves => Suit::iter()
+                .filter(|suit| has(*suit, Value::King) != has(*suit, Value::Ober))
+                .count(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(&self, count: usize, n: usize) -> bool {
+        match self {
+            Comparison::Equal => count == n,
+            Comparison::Less => count < n,
+            Comparison::LessOrEqual => count <= n,
+            Comparison::Greater => count > n,
+            Comparison::GreaterOrEqual => count >= n,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+    /// All cards are in the holder's hands
+    Holds(Holder, Vec<Card>),
+    /// King and ober of the suit in one hand
+    Pair(Holder, Suit),
+    Void(Holder, Suit),
+    /// Summed over the holder's hands
+    Count(Holder, Count, Comparison, usize),
+}
+
+impl Constraint {
+    pub fn matches(&self, deal: &[Vec<Card>; 4]) -> bool {
+        match self {
+            Constraint::Holds(holder, cards) => {
+                let hands = holder.hands(deal);
+                cards
+                    .iter()
+                    .all(|card| hands.iter().any(|hand| hand.contains(card)))
+            }
+            Constraint::Pair(holder, suit) => holder.hands(deal).iter().any(|hand| {
+                [Value::King, Value::Ober].iter().all(|value| {
+                    hand.contains(&Card {
+                        suit: *suit,
+                        value: *value,
+                    })
+                })
+            }),
+            Constraint::Void(holder, suit) => {
+                Constraint::Count(holder.clone(), Count::Suit(*suit), Comparison::Equal, 0)
+                    .matches(deal)
+            }
+            Constraint::Count(holder, count, comparison, n) => {
+                let total = holder
+                    .hands(deal)
+                    .iter()
+                    .map(|hand| count.of_hand(hand))
+                    .sum();
+                comparison.holds(total, *n)
+            }
+        }
+    }
+
+    /// Cards which have to be dealt to a single seat.
+    fn fixed_cards(&self) -> Option<(PlaceAtTable, Vec<Card>)> {
+        match self {
+            Constraint::Holds(Holder::Seat(seat), cards) => Some((seat.clone(), cards.clone())),
+            Constraint::Pair(Holder::Seat(seat), suit) => Some((
+                seat.clone(),
+                [Value::King, Value::Ober]
+                    .map(|value| Card { suit: *suit, value })
+                    .to_vec(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Constraints a deal has to satisfy, for practice and tests.
+ * Constraints are separated by `;` or new lines and all of them have to hold:
+ *
+ * ```text
+ * 0 holds r-K r-O    seat 0 holds these cards
+ * 0 pair r           seat 0 holds the red pair
+ * 1+3 pairs = 0      the party of seats 1 and 3 has no pair
+ * 1+3 halves >= 3    but three halves combined
+ * 2 void s           seat 2 has no bells
+ * 0 A >= 2           seat 0 holds at least two aces
+ * 1 r > 3            seat 1 holds more than three red cards
+ * ```
+ *
+ * Counts are `cards`, a suit, a value like `A` or `Z`, `pairs` and `halves`,
+ * compared with `=`, `<`, `<=`, `>` or `>=`. A half is a lone king or ober,
+ * a suit with both counts as a pair only.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DealConstraints {
+    pub constraints: Vec<Constraint>,
+    pub max_attempts: usize,
+}
+
+impl DealConstraints {
+    pub fn new(constraints: Vec<Constraint>) -> Self {
+        DealConstraints {
+            constraints,
+            max_attempts: MAX_ATTEMPTS,
+        }
+    }
+
+    pub fn matches(&self, deal: &[Vec<Card>; 4]) -> bool {
+        self.constraints
+            .iter()
+            .all(|constraint| constraint.matches(deal))
+    }
+
+    /**
+     * A deal satisfying all constraints, the same seed always results in the same deal.
+     * Cards a single seat has to hold are placed first, the rest is dealt until all constraints hold.
+     */
+    pub fn generate(&self, seed: u64) -> Result<[Vec<Card>; 4], Error> {
+        let mut fixed: [Vec<Card>; 4] = [vec![], vec![], vec![], vec![]];
+        for (seat, cards) in self.constraints.iter().filter_map(Constraint::fixed_cards) {
+            for card in cards {
+                if let Some(other) = fixed.iter().position(|hand| hand.contains(&card)) {
+                    if other != seat.0 as usize {
+                        return Err(Error::other(format!(
+                            "{} can not be dealt to seats {} and {}.",
+                            card, other, seat.0
+                        )));
+                    }
+                    continue;
+                }
+                fixed[seat.0 as usize].push(card);
+            }
+        }
+        if let Some(seat) = fixed.iter().position(|hand| hand.len() > 9) {
+            return Err(Error::other(format!(
+                "Seat {} has to hold more than nine cards.",
+                seat
+            )));
+        }
+
+        let rest: Vec<Card> = get_all_cards()
+            .into_iter()
+            .filter(|card| !fixed.iter().any(|hand| hand.contains(card)))
+            .collect();
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..self.max_attempts {
+            let mut deck = rest.clone();
+            deck.shuffle(&mut rng);
+            let mut deal = fixed.clone();
+            for hand in &mut deal {
+                let missing = 9 - hand.len();
+                hand.extend(deck.drain(..missing));
+                hand.sort();
+            }
+            if self.matches(&deal) {
+                return Ok(deal);
+            }
+        }
+        Err(Error::other(format!(
+            "No deal satisfying the constraints was found in {} attempts.",
+            self.max_attempts
+        )))
+    }
+}
+
+impl fmt::Display for Holder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Holder::Seat(seat) => write!(f, "{}", seat.0),
+            Holder::Party(party) => write!(f, "{}+{}", party.0, party.partner().0),
+        }
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Count::Cards => write!(f, "cards"),
+            Count::Suit(suit) => write!(f, "{}", suit_str(*suit)),
+            Count::Value(value) => write!(f, "{}", value_str(*value)),
+            Count::Pairs => write!(f, "pairs"),
+            Count::Halves => write!(f, "halves"),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Comparison::Equal => "=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Constraint::Holds(holder, cards) => {
+                write!(f, "{} holds", holder)?;
+                for card in cards {
+                    write!(f, " {}", card)?;
+                }
+                Ok(())
+            }
+            Constraint::Pair(holder, suit) => write!(f, "{} pair {}", holder, suit_str(*suit)),
+            Constraint::Void(holder, suit) => write!(f, "{} void {}", holder, suit_str(*suit)),
+            Constraint::Count(holder, count, comparison, n) => {
+                write!(f, "{} {} {} {}", holder, count, comparison, n)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DealConstraints {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let constraints: Vec<String> = self.constraints.iter().map(|c| c.to_string()).collect();
+        write!(f, "{}", constraints.join("; "))
+    }
+}
+
+impl FromStr for Holder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" | "1" | "2" | "3" => Ok(Holder::Seat(PlaceAtTable(s.parse().unwrap()))),
+            "0+2" | "2+0" => Ok(Holder::Party(PlaceAtTable(0))),
+            "1+3" | "3+1" => Ok(Holder::Party(PlaceAtTable(1))),
+            _ => Err(Error::other(format!(
+                "'{}' is neither a seat nor a party.",
+                s
+            ))),
+        }
+    }
+}
+
+impl FromStr for Count {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cards" => Ok(Count::Cards),
+            "pairs" => Ok(Count::Pairs),
+            "halves" => Ok(Count::Halves),
+            _ => parse_suit_str(s).map(Count::Suit).or_else(|_| {
+                Value::iter()
+                    .find(|value| value_str(*value) == s)
+                    .map(Count::Value)
+                    .ok_or_else(|| Error::other(format!("'{}' can not be counted.", s)))
+            }),
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "=" | "==" => Ok(Comparison::Equal),
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessOrEqual),
+            ">" => Ok(Comparison::Greater),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            _ => Err(Error::other(format!("'{}' is no comparison.", s))),
+        }
+    }
+}
+
+impl FromStr for Constraint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::other(format!("The constraint '{}' could not be parsed.", s));
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let (holder, rest) = parts.split_first().ok_or_else(err)?;
+        let holder: Holder = holder.parse()?;
+        Ok(match rest {
+            ["holds", cards @ ..] if !cards.is_empty() => Constraint::Holds(
+                holder,
+                cards
+                    .iter()
+                    .map(|card| card.parse())
+                    .collect::<Result<Vec<Card>, Error>>()?,
+            ),
+            ["pair", suit] => Constraint::Pair(holder, parse_suit_str(suit)?),
+            ["void", suit] => Constraint::Void(holder, parse_suit_str(suit)?),
+            [count, comparison, n] => Constraint::Count(
+                holder,
+                count.parse()?,
+                comparison.parse()?,
+                n.parse().map_err(|_| err())?,
+            ),
+            _ => return Err(err()),
+        })
+    }
+}
+
+impl FromStr for DealConstraints {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(DealConstraints::new(
+            s.split([';', '\n'])
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<Constraint>, Error>>()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_constraints() {
+        let spec = "0 holds r-K r-O; 1+3 pairs = 0; 1+3 halves >= 3; 2 void s; 0 A >= 2; 1 r > 3";
+        let constraints: DealConstraints = spec.parse().unwrap();
+        assert_eq!(constraints.constraints.len(), 6);
+        assert_eq!(constraints.to_string(), spec);
+        assert_eq!(
+            "3+1 cards <= 9\n\n0 pair g"
+                .parse::<DealConstraints>()
+                .unwrap(),
+            DealConstraints::new(vec![
+                Constraint::Count(
+                    Holder::Party(PlaceAtTable(1)),
+                    Count::Cards,
+                    Comparison::LessOrEqual,
+                    9
+                ),
+                Constraint::Pair(Holder::Seat(PlaceAtTable(0)), Suit::Green),
+            ])
+        );
+        for invalid in ["4 void r", "0 holds", "0 pair x", "0 r >> 2", "0+1 A = 1"] {
+            assert!(invalid.parse::<DealConstraints>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_generate_deal() {
+        let constraints: DealConstraints =
+            "0 pair r; 1+3 pairs = 0; 1+3 halves >= 3; 2 void s; 0 A >= 2"
+                .parse()
+                .unwrap();
+        let deal = constraints.generate(7).unwrap();
+        assert!(constraints.matches(&deal));
+        assert_eq!(deal, constraints.generate(7).unwrap());
+        let mut cards: Vec<Card> = deal.concat();
+        cards.sort();
+        cards.dedup();
+        assert_eq!(cards.len(), 36);
+        assert!(deal[2].iter().all(|card| card.suit != Suit::Bells));
+        // a full pair is no half
+        let hand: Vec<Card> = ["r-K", "r-O", "s-K"].map(|c| c.parse().unwrap()).to_vec();
+        assert_eq!(Count::Pairs.of_hand(&hand), 1);
+        assert_eq!(Count::Halves.of_hand(&hand), 1);
+
+        let conflict: DealConstraints = "0 holds r-A; 1 holds r-A".parse().unwrap();
+        assert!(conflict.generate(0).is_err());
+        let mut impossible: DealConstraints = "0 cards < 9".parse().unwrap();
+        impossible.max_attempts = 10;
+        assert!(impossible.generate(0).is_err());
+    }
+}
@@ -1,13 +1,13 @@
 use std::fmt::Debug;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::game::cards::{Card, Suit};
 use crate::game::player::PlaceAtTable;
 
 /// This is everything that happened since the last game state.
 /// Meant to broadcast implicit information about the game that follows actions
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameEvent {
     pub last_action: GameAction,
     /// Inner change that can not be known from single last action
@@ -24,7 +24,7 @@ pub enum GameEventPlayer {
 }
 
 /// Internal information after each action, i.e. questions, answers and trump changes.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameCallback {
     NewTrump(Suit),
     /// When asked again for half but is already trump
@@ -34,13 +34,13 @@ pub enum GameCallback {
 }
 
 /// This is what a player can create.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GameAction {
     pub action_type: ActionType,
     pub player: PlaceAtTable,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ActionType {
     Start,
     NewBid(i32),
@@ -55,16 +55,39 @@ pub enum ActionType {
     UndoAccept,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum QuestionType {
     Yours,
     YourHalf(Suit),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AnswerType {
     YesPair(Suit),
     NoPair,
     YesHalf(Suit),
     NoHalf(Suit),
 }
+
+/**
+ * All events which still have an effect on the game:
+ * undo requests and votes are dropped, as well as every action which was undone.
+ */
+pub fn effective_events(events: &[GameEvent]) -> Vec<GameEvent> {
+    let mut effective: Vec<GameEvent> = vec![];
+    let mut accepted = 0;
+    for event in events {
+        match event.last_action.action_type {
+            ActionType::UndoRequest | ActionType::UndoDecline => accepted = 0,
+            ActionType::UndoAccept => {
+                accepted += 1;
+                if accepted == 2 {
+                    effective.pop();
+                    accepted = 0;
+                }
+            }
+            _ => effective.push(event.clone()),
+        }
+    }
+    effective
+}
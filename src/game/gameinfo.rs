@@ -1,13 +1,13 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::game::cards::Card;
+use crate::game::cards::{Card, Suit};
 use crate::game::gameevent::{ActionType, GameAction, GameCallback, GameEvent, GameEventPlayer};
 use crate::game::gamestate::{FinishedTrick, GamePhase};
 use crate::game::player::{PlaceAtTable, Player};
 use crate::game::points::{points_pair, Points};
 use crate::game::{current_time_string, Game};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameMetaInfo {
     pub name: String,
     pub create_time: String,
@@ -15,6 +15,9 @@ pub struct GameMetaInfo {
     pub end_time: Option<String>,
     pub player_names: [String; 4],
     pub player_start_cards: [Vec<Card>; 4],
+    /// Only known if the cards were dealt from a seed.
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 impl GameMetaInfo {
@@ -31,6 +34,7 @@ impl GameMetaInfo {
                 players[2].cards.clone(),
                 players[3].cards.clone(),
             ],
+            seed: None,
         }
     }
 }
@@ -52,6 +56,14 @@ pub struct GameInfoPlayer {
     pub legal_actions: Vec<GameAction>,
 }
 
+/// Passed cards are only shown if `sees_passes`, all other events are public.
+pub fn redact_event(event: &GameEvent, sees_passes: bool) -> GameEventPlayer {
+    match event.last_action.action_type {
+        ActionType::Pass(_) if !sees_passes => GameEventPlayer::HiddenEvent,
+        _ => GameEventPlayer::PublicEvent(event.clone()),
+    }
+}
+
 impl GameInfoPlayer {
     pub fn from_game(game: Game, place: PlaceAtTable) -> Self {
         GameInfoPlayer {
@@ -72,24 +84,50 @@ impl GameInfoPlayer {
             bidding_history: game.state.bidding_history,
             current_trick: game.state.current_trick,
             last_trick: game.state.all_tricks.clone().last().cloned(),
-            last_event: match game.all_events.last() {
+            last_event: game
+                .all_events
+                .last()
+                .map(|event| redact_event(event, false)),
+            legal_actions: game.legal.to_vec(),
+        }
+    }
+}
+
+impl GameInfoPlayer {
+    /// The view without the dealt cards of the meta info, actions and cards in notation.
+    pub fn to_json(&self) -> serde_json::Value {
+        let cards =
+            |cards: &[Card]| -> Vec<String> { cards.iter().map(|card| card.to_string()).collect() };
+        serde_json::json!({
+            "name": self.meta_info.name,
+            "players_pressed_start": self.players_pressed_start,
+            "players_from_perspective": self.players_from_perspective,
+            "player_at_turn": self.player_at_turn,
+            "own_cards": self.own_cards.as_deref().map(cards),
+            "players_cards_number_perspective": self.players_cards_number_perspective,
+            "game_phase": format!("{:?}", self.game_phase),
+            "bidding_history": self.bidding_history.iter()
+                .map(|(action, place)| format!("{} {}", place.0, action))
+                .collect::<Vec<_>>(),
+            "current_trick": cards(&self.current_trick),
+            "last_trick": self.last_trick.as_ref().map(|trick| serde_json::json!({
+                "cards": cards(&trick.cards),
+                "winner": trick.winner.0,
+            })),
+            "last_event": match &self.last_event {
+                Some(GameEventPlayer::PublicEvent(event)) => Some(event.last_action.to_string()),
+                Some(GameEventPlayer::HiddenEvent) => Some(String::from("hidden")),
                 None => None,
-                Some(event) => {
-                    //hide last event if cards were passed
-                    if let ActionType::Pass(_cards) = event.last_action.action_type.clone() {
-                        Some(GameEventPlayer::HiddenEvent)
-                    } else {
-                        Some(GameEventPlayer::PublicEvent((*event).clone()))
-                    }
-                }
             },
-            legal_actions: game.legal_actions.clone(),
-        }
+            "legal_actions": self.legal_actions.iter()
+                .map(|action| action.to_string())
+                .collect::<Vec<_>>(),
+        })
     }
 }
 
 /// Everything the database needs to know
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameFinishedInfo {
     pub info: GameMetaInfo,
     pub game_value: Points,
@@ -113,11 +151,10 @@ impl From<Game> for GameFinishedInfo {
         }
         let no_one_played = game.state.value.0 == 115;
 
-        let mut players_points = [Points(0); 4];
+        let players_points = players_points(&game.state.all_tricks, &game.all_events);
         let mut players_tricks: [Vec<FinishedTrick>; 4] = [vec![], vec![], vec![], vec![]];
         for trick in &game.state.all_tricks {
             players_tricks[trick.winner.0 as usize].push(trick.clone());
-            players_points[trick.winner.0 as usize] += trick.points;
         }
         let tricks_party_zero = players_tricks[0].len() + players_tricks[2].len();
         let schwarz_game = tricks_party_zero == 0 || tricks_party_zero == 9;
@@ -127,18 +164,12 @@ impl From<Game> for GameFinishedInfo {
         let mut passed_cards: Option<(Vec<Card>, Vec<Card>)> = None;
         let mut after_passing: Option<[Vec<Card>; 4]> = None;
         if !no_one_played {
-            let mut playing_player = PlaceAtTable(0);
+            let playing_player =
+                find_playing_player(&game.all_events, game.state.value).unwrap_or(PlaceAtTable(0));
+            playing_party = Some(playing_player.party());
             let mut passed_forth: Option<Vec<Card>> = None;
             let mut passed_back: Option<Vec<Card>> = None;
             for event in &game.all_events {
-                if ActionType::NewBid(game.state.value.0) == event.last_action.action_type {
-                    playing_player = event.last_action.player.clone();
-                    playing_party = Some(event.last_action.player.party());
-                }
-                if let Some(GameCallback::NewTrump(suit)) = event.callback {
-                    players_points[event.last_action.player.clone().0 as usize] +=
-                        points_pair(suit);
-                }
                 if let ActionType::Pass(cards) = event.last_action.action_type.clone() {
                     if passed_forth.is_none() {
                         passed_forth = Some(cards);
@@ -191,7 +222,75 @@ impl From<Game> for GameFinishedInfo {
     }
 }
 
+/// Points of every player from tricks and the pairs they announced.
+fn players_points(tricks: &[FinishedTrick], events: &[GameEvent]) -> [Points; 4] {
+    let mut players_points = [Points(0); 4];
+    for trick in tricks {
+        players_points[trick.winner.0 as usize] += trick.points;
+    }
+    for event in events {
+        if let Some(GameCallback::NewTrump(suit)) = event.callback {
+            players_points[event.last_action.player.0 as usize] += points_pair(suit);
+        }
+    }
+    players_points
+}
+
+/// The player who made the last bid with the final value.
+fn find_playing_player(events: &[GameEvent], value: Points) -> Option<PlaceAtTable> {
+    events
+        .iter()
+        .rfind(|event| event.last_action.action_type == ActionType::NewBid(value.0))
+        .map(|event| event.last_action.player.clone())
+}
+
 impl GameFinishedInfo {
+    pub fn players_points(&self) -> [Points; 4] {
+        players_points(&self.tricks, &self.all_events)
+    }
+
+    /**
+     * Result for one party, 0 or 1: the game value, positive if the party won,
+     * or the difference in points if no one played.
+     */
+    pub fn party_score(&self, party: u8) -> f64 {
+        match (&self.playing_party, self.won) {
+            (Some(playing), Some(won)) => {
+                let score = if won { 1.0 } else { -1.0 } * self.game_value.0 as f64;
+                if playing.0 % 2 == party {
+                    score
+                } else {
+                    -score
+                }
+            }
+            _ => {
+                let points = self.players_points();
+                let own = points[party as usize].0 + points[party as usize + 2].0;
+                let other = points[1 - party as usize].0 + points[3 - party as usize].0;
+                (own - other) as f64
+            }
+        }
+    }
+
+    /// None if no_one_played
+    pub fn playing_player(&self) -> Option<PlaceAtTable> {
+        if self.no_one_played {
+            return None;
+        }
+        find_playing_player(&self.all_events, self.game_value)
+    }
+
+    /// The suits each player announced as trump, in order of announcement.
+    pub fn players_announced(&self) -> [Vec<Suit>; 4] {
+        let mut announced: [Vec<Suit>; 4] = [vec![], vec![], vec![], vec![]];
+        for event in &self.all_events {
+            if let Some(GameCallback::NewTrump(suit)) = event.callback {
+                announced[event.last_action.player.0 as usize].push(suit);
+            }
+        }
+        announced
+    }
+
     pub fn set_times(&mut self, created: String, started: String, ended: String) {
         self.info.create_time = created;
         self.info.start_time = Some(started);
@@ -1,11 +1,11 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::game::cards::{Card, Suit};
 use crate::game::gameevent::ActionType;
 use crate::game::player::{PlaceAtTable, Player};
 use crate::game::points::Points;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GamePhase {
     WaitingForStart,
     Bidding,
@@ -20,14 +20,15 @@ pub enum GamePhase {
     PendingUndo(Box<GamePhase>),
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FinishedTrick {
     pub cards: [Card; 4],
     pub winner: PlaceAtTable,
     pub points: Points,
 }
 
-#[derive(Debug, Clone)]
+/// Compares and hashes everything, see `fingerprint` for the game relevant part.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct GameState {
     pub phase: GamePhase,
     pub started: bool,
@@ -42,11 +43,13 @@ pub struct GameState {
     pub value: Points,
     pub all_tricks: Vec<FinishedTrick>,
     pub current_trick: Vec<Card>,
+    /// Zobrist hash of the game relevant part, updated by `Game::apply_action`
+    pub(crate) hash: u64,
 }
 
 impl GameState {
     pub fn create(players: [Player; 4]) -> Self {
-        GameState {
+        let mut state = GameState {
             started: false,
             players_started: vec![],
             players_accept_undo: vec![],
@@ -60,7 +63,10 @@ impl GameState {
             players,
             all_tricks: vec![],
             current_trick: vec![],
-        }
+            hash: 0,
+        };
+        state.refresh_fingerprint();
+        state
     }
     pub fn player_at_turn(&self) -> &Player {
         &self.players[self.player_at_turn.0 as usize]
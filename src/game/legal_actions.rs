@@ -3,224 +3,575 @@ use itertools::Itertools;
 use crate::game::cards::{allowed_cards, Card, Suit};
 use crate::game::gameevent::{ActionType, AnswerType, GameAction, QuestionType};
 use crate::game::gamestate::GamePhase;
-use crate::game::player::{Player, PlayerTrumpPossibilities};
+use crate::game::player::{PlaceAtTable, Player, PlayerTrumpPossibilities};
 use crate::game::points::Points;
 use crate::game::{cards, Game};
 
+/// Bids from `min` to `max` in steps of 5.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidRange {
+    pub min: i32,
+    pub max: i32,
+    /// Only while bidding, raising is done by playing a card
+    pub can_stop: bool,
+    /// Raises are listed from the highest value down
+    pub descending: bool,
+}
+
+impl BidRange {
+    pub fn contains(&self, value: i32) -> bool {
+        value >= self.min && value <= self.max && (value - self.min) % 5 == 0
+    }
+
+    pub fn len(&self) -> usize {
+        match self.max >= self.min {
+            true => ((self.max - self.min) / 5 + 1) as usize,
+            false => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = i32>> {
+        let min = self.min;
+        let values = (0..self.len() as i32).map(move |step| min + 5 * step);
+        match self.descending {
+            true => Box::new(values.rev()),
+            false => Box::new(values),
+        }
+    }
+}
+
+/// Any `count` cards of the hand, passed sorted from highest to lowest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassChoice {
+    pub hand: Vec<Card>,
+    pub count: usize,
+}
+
+impl PassChoice {
+    pub fn contains(&self, cards: &[Card]) -> bool {
+        cards.len() == self.count
+            && cards.windows(2).all(|pair| pair[0] > pair[1])
+            && cards.iter().all(|card| self.hand.contains(card))
+    }
+
+    pub fn len(&self) -> usize {
+        if self.count > self.hand.len() {
+            return 0;
+        }
+        (0..self.count).fold(1, |acc, i| acc * (self.hand.len() - i) / (i + 1))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn combinations(&self) -> impl Iterator<Item = Vec<Card>> + '_ {
+        self.hand
+            .iter()
+            .cloned()
+            .combinations(self.count)
+            .map(|comb| comb.into_iter().sorted().rev().collect())
+    }
+}
+
+/**
+ * All legal actions of a state without materializing every bid and pass.
+ * Iterating yields the same actions in the same order as `Game::legal_actions`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegalActions {
+    /// Seat at turn, the one who bids, passes, asks, answers or plays
+    pub player: PlaceAtTable,
+    /// Seats which did not start yet
+    pub starts: Vec<PlaceAtTable>,
+    pub bids: Option<BidRange>,
+    pub pass: Option<PassChoice>,
+    /// Announcements and questions
+    pub questions: Vec<ActionType>,
+    pub cards: Vec<Card>,
+    pub answers: Vec<AnswerType>,
+    /// Seats which may still accept or decline a pending undo
+    pub undo_votes: Vec<PlaceAtTable>,
+    pub undo_request: Option<PlaceAtTable>,
+}
+
+impl LegalActions {
+    pub(crate) fn empty(player: PlaceAtTable) -> Self {
+        LegalActions {
+            player,
+            starts: vec![],
+            bids: None,
+            pass: None,
+            questions: vec![],
+            cards: vec![],
+            answers: vec![],
+            undo_votes: vec![],
+            undo_request: None,
+        }
+    }
+
+    pub fn is_legal(&self, action: &GameAction) -> bool {
+        let at_turn = action.player == self.player;
+        match &action.action_type {
+            ActionType::Start => self.starts.contains(&action.player),
+            ActionType::StopBidding => at_turn && self.bids.as_ref().is_some_and(|b| b.can_stop),
+            ActionType::NewBid(value) => {
+                at_turn && self.bids.as_ref().is_some_and(|b| b.contains(*value))
+            }
+            ActionType::Pass(cards) => {
+                at_turn && self.pass.as_ref().is_some_and(|p| p.contains(cards))
+            }
+            ActionType::CardPlayed(card) => at_turn && self.cards.contains(card),
+            ActionType::Question(_) | ActionType::AnnounceTrump(_) => {
+                at_turn && self.questions.contains(&action.action_type)
+            }
+            ActionType::Answer(answer) => at_turn && self.answers.contains(answer),
+            ActionType::UndoRequest => self.undo_request.as_ref() == Some(&action.player),
+            ActionType::UndoAccept | ActionType::UndoDecline => {
+                self.undo_votes.contains(&action.player)
+            }
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.starts.len()
+            + self
+                .bids
+                .as_ref()
+                .map_or(0, |b| b.len() + b.can_stop as usize)
+            + self.pass.as_ref().map_or(0, |p| p.len())
+            + self.questions.len()
+            + self.cards.len()
+            + self.answers.len()
+            + 2 * self.undo_votes.len()
+            + self.undo_request.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = GameAction> + '_ {
+        let at_turn = move |action_type: ActionType| GameAction {
+            action_type,
+            player: self.player.clone(),
+        };
+        let starts = self.starts.iter().map(|player| GameAction {
+            action_type: ActionType::Start,
+            player: player.clone(),
+        });
+        let stop = self
+            .bids
+            .iter()
+            .filter(|b| b.can_stop)
+            .map(move |_| at_turn(ActionType::StopBidding));
+        let bids = self
+            .bids
+            .iter()
+            .flat_map(|b| b.values())
+            .map(move |value| at_turn(ActionType::NewBid(value)));
+        let passes = self
+            .pass
+            .iter()
+            .flat_map(|p| p.combinations())
+            .map(move |cards| at_turn(ActionType::Pass(cards)));
+        let questions = self.questions.iter().cloned().map(at_turn);
+        let cards = self
+            .cards
+            .iter()
+            .map(move |card| at_turn(ActionType::CardPlayed(card.clone())));
+        let answers = self
+            .answers
+            .iter()
+            .map(move |answer| at_turn(ActionType::Answer(answer.clone())));
+        let undo_votes = self.undo_votes.iter().flat_map(|player| {
+            [ActionType::UndoAccept, ActionType::UndoDecline].map(|action_type| GameAction {
+                action_type,
+                player: player.clone(),
+            })
+        });
+        let undo_request = self.undo_request.iter().map(|player| GameAction {
+            action_type: ActionType::UndoRequest,
+            player: player.clone(),
+        });
+        starts
+            .chain(stop)
+            .chain(bids)
+            .chain(passes)
+            .chain(questions)
+            .chain(cards)
+            .chain(answers)
+            .chain(undo_votes)
+            .chain(undo_request)
+    }
+
+    pub fn to_vec(&self) -> Vec<GameAction> {
+        self.iter().collect()
+    }
+}
+
 impl GamePhase {
     pub fn legal_actions(&self, game: &Game) -> Vec<GameAction> {
+        self.compact_legal_actions(game).to_vec()
+    }
+
+    /// Legal actions of the phase, without undo requests.
+    pub fn compact_legal_actions(&self, game: &Game) -> LegalActions {
+        let mut legal = LegalActions::empty(game.state.player_at_turn.clone());
         match self {
             GamePhase::WaitingForStart => {
-                let mut start: Vec<GameAction> = vec![];
-                for player in &game.state.players {
-                    let mut has_started = false;
-                    for possibly_same_player in game.state.players_started.clone() {
-                        if player.place_at_table == possibly_same_player {
-                            has_started = true;
-                        }
-                    }
-                    if !has_started {
-                        start.push(GameAction {
-                            action_type: ActionType::Start,
-                            player: player.place_at_table.clone(),
-                        })
-                    }
-                }
-                start
+                legal.starts = game
+                    .state
+                    .players
+                    .iter()
+                    .map(|player| player.place_at_table.clone())
+                    .filter(|place| !game.state.players_started.contains(place))
+                    .collect();
+            }
+            GamePhase::Bidding => legal.bids = Some(bid_range(game, false)),
+            GamePhase::PassingForth | GamePhase::PassingBack => {
+                legal.pass = Some(pass_choice(game))
             }
-            GamePhase::Bidding => legal_bidding(game),
-            GamePhase::PassingForth => legal_passing(game),
-            GamePhase::PassingBack => legal_passing(game),
             GamePhase::Raising => {
-                let mut allowed = legal_bidding(game);
-                allowed.reverse();
-                allowed.pop();
-                allowed.extend(legal_cards(game));
-                allowed
+                legal.bids = Some(bid_range(game, true));
+                legal.cards = playable_cards(game);
             }
             GamePhase::StartTrick => {
-                let mut allowed = vec![];
-                allowed.extend(legal_question(game));
-                allowed.extend(legal_cards(game));
-                allowed
+                legal.questions = questions(game.state.player_at_turn(), game);
+                legal.cards = playable_cards(game);
             }
-            GamePhase::Trick => legal_cards(game),
-            GamePhase::AnsweringPair => legal_answer(game),
-            GamePhase::AnsweringHalf(_suit) => legal_answer(game),
-            GamePhase::Ended => {
-                vec![]
+            GamePhase::Trick => legal.cards = playable_cards(game),
+            // without a question there is nothing to answer
+            GamePhase::AnsweringPair | GamePhase::AnsweringHalf(_) => {
+                legal.answers = answers(game).unwrap_or_default()
             }
+            GamePhase::Ended => {}
             GamePhase::PendingUndo(_previous_phase) => {
-                let mut undo: Vec<GameAction> = vec![];
                 let next_player = game.last_state.clone().unwrap().player_at_turn.next();
                 let next_player_partner = next_player.partner();
-                let players_to_ask = vec![next_player, next_player_partner];
-                for player in players_to_ask {
-                    let mut has_accepted = false;
-                    for possibly_accepted in &game.state.players_accept_undo {
-                        if player == *possibly_accepted {
-                            has_accepted = true;
-                        }
-                    }
-                    if !has_accepted {
-                        undo.push(GameAction {
-                            action_type: ActionType::UndoAccept,
-                            player: player.clone(),
-                        });
-                        undo.push(GameAction {
-                            action_type: ActionType::UndoDecline,
-                            player,
-                        });
-                    }
-                }
-                undo
+                legal.undo_votes = [next_player, next_player_partner]
+                    .into_iter()
+                    .filter(|player| !game.state.players_accept_undo.contains(player))
+                    .collect();
             }
         }
+        legal
     }
 }
 
-pub fn legal_bidding(game: &Game) -> Vec<GameAction> {
-    let start_value = game.state.value + Points(5);
-    let mut allowed_actions = vec![GameAction {
-        action_type: ActionType::StopBidding,
-        player: game.state.player_at_turn.clone(),
-    }];
-    for allowed_value in (start_value.0..=420).step_by(5) {
-        allowed_actions.push(GameAction {
-            action_type: ActionType::NewBid(allowed_value),
-            player: game.state.player_at_turn.clone(),
-        })
+/// Bids above the current value, raises can't be stopped and are listed from the top.
+fn bid_range(game: &Game, raising: bool) -> BidRange {
+    BidRange {
+        min: (game.state.value + Points(5)).0,
+        max: 420,
+        can_stop: !raising,
+        descending: raising,
     }
-    allowed_actions
 }
 
-pub fn legal_passing(game: &Game) -> Vec<GameAction> {
-    let mut actions: Vec<GameAction> = vec![];
-    for comb in game
-        .state
-        .player_at_turn()
-        .cards
-        .clone()
-        .into_iter()
-        .combinations(4)
-    {
-        let sorted: Vec<Card> = comb.clone().into_iter().sorted().rev().collect();
-        actions.push(GameAction {
-            action_type: ActionType::Pass(sorted),
-            player: game.state.player_at_turn.clone(),
-        })
+fn pass_choice(game: &Game) -> PassChoice {
+    PassChoice {
+        hand: game.state.player_at_turn().cards.clone(),
+        count: 4,
     }
-    actions
 }
 
-pub fn legal_cards(game: &Game) -> Vec<GameAction> {
-    let mut actions: Vec<GameAction> = vec![];
-
-    let cards = game.state.player_at_turn().cards.clone();
-    let mut players_cards: Vec<&Card> = vec![];
-    for c in &cards {
-        players_cards.push(c);
-    }
+fn playable_cards(game: &Game) -> Vec<Card> {
+    let cards = &game.state.player_at_turn().cards;
     let mut trick = game.state.current_trick.clone();
     if trick.len() == 4 {
         trick = vec![];
     }
-    let trump = game.state.trump;
-    let first_trick = players_cards.len() == 9;
-
-    for card in allowed_cards(trick.iter().collect(), players_cards, trump, first_trick) {
-        actions.push(GameAction {
-            action_type: ActionType::CardPlayed(card.clone()),
-            player: game.state.player_at_turn.clone(),
-        })
-    }
-
-    actions
+    let first_trick = cards.len() == 9;
+    allowed_cards(
+        trick.iter().collect(),
+        cards.iter().collect(),
+        game.state.trump,
+        first_trick,
+    )
+    .into_iter()
+    .cloned()
+    .collect()
 }
 
-pub fn legal_question(game: &Game) -> Vec<GameAction> {
-    let player: &Player = game.state.player_at_turn();
-    let mut actions: Vec<GameAction> = vec![];
-    let cards = player.cards.clone();
-    let trump = player.trump.clone();
-    let mut own_actions = vec![];
-    for suit in cards::pairs(cards) {
-        if game.state.trump_called.contains(&suit) {
-            continue;
-        }
-        own_actions.push(GameAction {
-            action_type: ActionType::AnnounceTrump(suit),
-            player: player.place_at_table.clone(),
-        })
-    }
-    let yours_actions = vec![GameAction {
-        action_type: ActionType::Question(QuestionType::Yours),
-        player: player.place_at_table.clone(),
-    }];
-    let mut ours_actions = vec![];
-    for suit in [Suit::Red, Suit::Bells, Suit::Acorns, Suit::Green] {
-        ours_actions.push(GameAction {
-            action_type: ActionType::Question(QuestionType::YourHalf(suit)),
-            player: player.place_at_table.clone(),
-        })
-    }
-    match trump {
-        PlayerTrumpPossibilities::Own => {
-            actions.extend(own_actions);
-            actions.extend(yours_actions);
-            actions.extend(ours_actions);
-        }
-        PlayerTrumpPossibilities::Yours => {
-            actions.extend(yours_actions);
-            actions.extend(ours_actions);
-        }
-        PlayerTrumpPossibilities::Ours => {
-            actions.extend(ours_actions);
-        }
+fn questions(player: &Player, game: &Game) -> Vec<ActionType> {
+    let own_actions = cards::pairs(player.cards.clone())
+        .into_iter()
+        .filter(|suit| !game.state.trump_called.contains(suit))
+        .map(ActionType::AnnounceTrump);
+    let yours_actions = [ActionType::Question(QuestionType::Yours)];
+    let ours_actions = [Suit::Red, Suit::Bells, Suit::Acorns, Suit::Green]
+        .map(|suit| ActionType::Question(QuestionType::YourHalf(suit)));
+    match player.trump {
+        PlayerTrumpPossibilities::Own => own_actions
+            .chain(yours_actions)
+            .chain(ours_actions)
+            .collect(),
+        PlayerTrumpPossibilities::Yours => yours_actions.into_iter().chain(ours_actions).collect(),
+        PlayerTrumpPossibilities::Ours => ours_actions.to_vec(),
     }
-    actions
 }
 
-pub fn legal_answer(game: &Game) -> Vec<GameAction> {
-    let last_event = game.all_events.last().unwrap();
+/// None if the last action was no question.
+fn answers(game: &Game) -> Option<Vec<AnswerType>> {
+    let last_event = game.all_events.last()?;
     let cards = game.state.player_at_turn().cards.clone();
-    let mut actions: Vec<GameAction> = vec![];
-    match last_event.last_action.action_type {
+    Some(match last_event.last_action.action_type {
         ActionType::Question(QuestionType::Yours) => {
-            for suit in cards::pairs(cards) {
-                //don't allow double calling
-                if game.state.trump_called.contains(&suit) {
-                    continue;
-                }
-                actions.push(GameAction {
-                    action_type: ActionType::Answer(AnswerType::YesPair(suit)),
-                    player: game.state.player_at_turn.clone(),
-                });
-            }
-            if actions.is_empty() {
-                actions.push(GameAction {
-                    action_type: ActionType::Answer(AnswerType::NoPair),
-                    player: game.state.player_at_turn.clone(),
-                })
+            //don't allow double calling
+            let pairs: Vec<AnswerType> = cards::pairs(cards)
+                .into_iter()
+                .filter(|suit| !game.state.trump_called.contains(suit))
+                .map(AnswerType::YesPair)
+                .collect();
+            if pairs.is_empty() {
+                vec![AnswerType::NoPair]
+            } else {
+                pairs
             }
         }
         ActionType::Question(QuestionType::YourHalf(suit)) => {
             if cards::halves(cards).contains(&suit) {
-                actions.push(GameAction {
-                    action_type: ActionType::Answer(AnswerType::YesHalf(suit)),
-                    player: game.state.player_at_turn.clone(),
-                });
+                vec![AnswerType::YesHalf(suit)]
             } else {
-                actions.push(GameAction {
-                    action_type: ActionType::Answer(AnswerType::NoHalf(suit)),
-                    player: game.state.player_at_turn.clone(),
+                vec![AnswerType::NoHalf(suit)]
+            }
+        }
+        _ => return None,
+    })
+}
+
+fn at_turn(game: &Game, action_types: impl IntoIterator<Item = ActionType>) -> Vec<GameAction> {
+    action_types
+        .into_iter()
+        .map(|action_type| GameAction {
+            action_type,
+            player: game.state.player_at_turn.clone(),
+        })
+        .collect()
+}
+
+pub fn legal_bidding(game: &Game) -> Vec<GameAction> {
+    let range = bid_range(game, false);
+    at_turn(
+        game,
+        std::iter::once(ActionType::StopBidding).chain(range.values().map(ActionType::NewBid)),
+    )
+}
+
+pub fn legal_passing(game: &Game) -> Vec<GameAction> {
+    at_turn(game, pass_choice(game).combinations().map(ActionType::Pass))
+}
+
+pub fn legal_cards(game: &Game) -> Vec<GameAction> {
+    at_turn(
+        game,
+        playable_cards(game).into_iter().map(ActionType::CardPlayed),
+    )
+}
+
+pub fn legal_question(game: &Game) -> Vec<GameAction> {
+    at_turn(game, questions(game.state.player_at_turn(), game))
+}
+
+/// None if no question was asked.
+pub fn legal_answer(game: &Game) -> Option<Vec<GameAction>> {
+    answers(game).map(|answers| at_turn(game, answers.into_iter().map(ActionType::Answer)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The legal actions as they were enumerated before the compact form, kept as the reference.
+    fn enumerate_legal_actions(game: &Game) -> Vec<GameAction> {
+        let at_turn = game.state.player_at_turn.clone();
+        let action = |action_type| GameAction {
+            action_type,
+            player: at_turn.clone(),
+        };
+        let bidding = || {
+            let mut actions = vec![action(ActionType::StopBidding)];
+            for value in ((game.state.value + Points(5)).0..=420).step_by(5) {
+                actions.push(action(ActionType::NewBid(value)));
+            }
+            actions
+        };
+        let cards = || {
+            let hand: Vec<&Card> = game.state.player_at_turn().cards.iter().collect();
+            let mut trick = game.state.current_trick.clone();
+            if trick.len() == 4 {
+                trick = vec![];
+            }
+            let first_trick = hand.len() == 9;
+            allowed_cards(trick.iter().collect(), hand, game.state.trump, first_trick)
+                .into_iter()
+                .map(|card| action(ActionType::CardPlayed(card.clone())))
+                .collect::<Vec<GameAction>>()
+        };
+        let questions = || {
+            let player = game.state.player_at_turn();
+            let own: Vec<GameAction> = cards::pairs(player.cards.clone())
+                .into_iter()
+                .filter(|suit| !game.state.trump_called.contains(suit))
+                .map(|suit| action(ActionType::AnnounceTrump(suit)))
+                .collect();
+            let yours = vec![action(ActionType::Question(QuestionType::Yours))];
+            let ours: Vec<GameAction> = [Suit::Red, Suit::Bells, Suit::Acorns, Suit::Green]
+                .map(|suit| action(ActionType::Question(QuestionType::YourHalf(suit))))
+                .to_vec();
+            match player.trump {
+                PlayerTrumpPossibilities::Own => [own, yours, ours].concat(),
+                PlayerTrumpPossibilities::Yours => [yours, ours].concat(),
+                PlayerTrumpPossibilities::Ours => ours,
+            }
+        };
+        let answers = || {
+            let hand = game.state.player_at_turn().cards.clone();
+            match game.all_events.last().unwrap().last_action.action_type {
+                ActionType::Question(QuestionType::Yours) => {
+                    let mut actions: Vec<GameAction> = cards::pairs(hand)
+                        .into_iter()
+                        .filter(|suit| !game.state.trump_called.contains(suit))
+                        .map(|suit| action(ActionType::Answer(AnswerType::YesPair(suit))))
+                        .collect();
+                    if actions.is_empty() {
+                        actions.push(action(ActionType::Answer(AnswerType::NoPair)));
+                    }
+                    actions
+                }
+                ActionType::Question(QuestionType::YourHalf(suit)) => {
+                    if cards::halves(hand).contains(&suit) {
+                        vec![action(ActionType::Answer(AnswerType::YesHalf(suit)))]
+                    } else {
+                        vec![action(ActionType::Answer(AnswerType::NoHalf(suit)))]
+                    }
+                }
+                _ => panic!("Trying to find answers without question asked!"),
+            }
+        };
+        let mut actions = match &game.state.phase {
+            GamePhase::WaitingForStart => game
+                .state
+                .players
+                .iter()
+                .filter(|player| !game.state.players_started.contains(&player.place_at_table))
+                .map(|player| GameAction {
+                    action_type: ActionType::Start,
+                    player: player.place_at_table.clone(),
                 })
+                .collect(),
+            GamePhase::Bidding => bidding(),
+            GamePhase::PassingForth | GamePhase::PassingBack => game
+                .state
+                .player_at_turn()
+                .cards
+                .clone()
+                .into_iter()
+                .combinations(4)
+                .map(|pass| action(ActionType::Pass(pass.into_iter().sorted().rev().collect())))
+                .collect(),
+            GamePhase::Raising => {
+                let mut allowed = bidding();
+                allowed.reverse();
+                allowed.pop();
+                allowed.extend(cards());
+                allowed
             }
+            GamePhase::StartTrick => [questions(), cards()].concat(),
+            GamePhase::Trick => cards(),
+            GamePhase::AnsweringPair | GamePhase::AnsweringHalf(_) => answers(),
+            GamePhase::Ended => vec![],
+            GamePhase::PendingUndo(_) => {
+                let next = game.last_state.clone().unwrap().player_at_turn.next();
+                let mut undo = vec![];
+                for player in [next.clone(), next.partner()] {
+                    if !game.state.players_accept_undo.contains(&player) {
+                        undo.push(GameAction {
+                            action_type: ActionType::UndoAccept,
+                            player: player.clone(),
+                        });
+                        undo.push(GameAction {
+                            action_type: ActionType::UndoDecline,
+                            player,
+                        });
+                    }
+                }
+                undo
+            }
+        };
+        let undo_allowed = match game.state.phase {
+            GamePhase::WaitingForStart
+            | GamePhase::Ended
+            | GamePhase::PassingBack
+            | GamePhase::Raising
+            | GamePhase::PendingUndo(_) => false,
+            GamePhase::Bidding => game.state.value != Points(115),
+            _ => true,
+        };
+        if let (true, Some(last_state)) = (undo_allowed, &game.last_state) {
+            actions.push(GameAction {
+                action_type: ActionType::UndoRequest,
+                player: last_state.player_at_turn.clone(),
+            });
         }
-        _ => {
-            println!("{:?}", game);
-            panic!("Trying to find answers without question asked!")
+        actions
+    }
+
+    #[test]
+    fn test_compact_matches_enumeration() {
+        for seed in 0..20 {
+            let names = ["A", "B", "C", "D"].map(String::from);
+            let mut game = Game::new_seeded(String::from("Legal"), names, seed);
+            let mut step = 0;
+            while !game.ended() {
+                let legal = enumerate_legal_actions(&game);
+                assert_eq!(game.legal.to_vec(), legal);
+                assert_eq!(game.legal_actions(), legal);
+                assert_eq!(game.legal.count(), legal.len());
+                assert!(legal.iter().all(|action| game.legal.is_legal(action)));
+                // passes in another order are not legal, as before
+                if let Some(ActionType::Pass(cards)) = legal.first().map(|a| &a.action_type) {
+                    let mut reordered = cards.clone();
+                    reordered.reverse();
+                    assert!(!game.legal.is_legal(&GameAction {
+                        action_type: ActionType::Pass(reordered),
+                        player: game.state.player_at_turn.clone(),
+                    }));
+                }
+                step += 1;
+                game = game
+                    .apply_action(legal[(seed as usize * 7 + step * 13) % legal.len()].clone())
+                    .unwrap();
+            }
         }
     }
-    actions
+
+    #[test]
+    fn test_illegal_actions() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let game = Game::new_seeded(String::from("Legal"), names, 1)
+            .apply_notation("start; start; start; start; bid 140")
+            .unwrap();
+        let legal = &game.legal;
+        let bid = |value, seat| GameAction {
+            action_type: ActionType::NewBid(value),
+            player: PlaceAtTable(seat),
+        };
+        assert!(legal.is_legal(&bid(145, 1)));
+        assert!(legal.is_legal(&bid(420, 1)));
+        assert!(!legal.is_legal(&bid(140, 1)));
+        assert!(!legal.is_legal(&bid(147, 1)));
+        assert!(!legal.is_legal(&bid(425, 1)));
+        assert!(!legal.is_legal(&bid(145, 2)));
+        assert!(legal.is_legal(&GameAction {
+            action_type: ActionType::UndoRequest,
+            player: PlaceAtTable(0),
+        }));
+        assert_eq!(legal.count(), 1 + 56 + 1);
+    }
 }
@@ -0,0 +1,286 @@
+use std::fmt;
+use std::io::Error;
+use std::str::FromStr;
+
+use crate::game::cards::{Card, Suit, Value};
+use crate::game::gameevent::{ActionType, AnswerType, GameAction, QuestionType};
+use crate::game::player::PlaceAtTable;
+use crate::game::Game;
+
+/// Short suit notation, the same letters as used in the card notation.
+pub fn suit_str(suit: Suit) -> &'static str {
+    match suit {
+        Suit::Red => "r",
+        Suit::Bells => "s",
+        Suit::Acorns => "e",
+        Suit::Green => "g",
+    }
+}
+
+/// Short value notation, the same letters as used in the card notation.
+pub fn value_str(value: Value) -> &'static str {
+    match value {
+        Value::Ace => "A",
+        Value::Ten => "Z",
+        Value::King => "K",
+        Value::Ober => "O",
+        Value::Unter => "U",
+        Value::Nine => "9",
+        Value::Eight => "8",
+        Value::Seven => "7",
+        Value::Six => "6",
+    }
+}
+
+pub fn parse_suit_str(suit: &str) -> Result<Suit, Error> {
+    match suit {
+        "r" => Ok(Suit::Red),
+        "s" => Ok(Suit::Bells),
+        "e" => Ok(Suit::Acorns),
+        "g" => Ok(Suit::Green),
+        _ => Err(Error::other(format!("unknown suit '{}'", suit))),
+    }
+}
+
+/**
+ * Compact text notation for actions:
+ * `start`, `bid 140`, `stop`, `pass e-A e-O g-A g-9`, `play e-A`,
+ * `ask pair`, `ask half r`, `announce r`, `answer pair r`, `answer nopair`,
+ * `answer half r`, `answer nohalf r`, `undo`, `undo accept` and `undo decline`.
+ */
+impl fmt::Display for ActionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionType::Start => write!(f, "start"),
+            ActionType::NewBid(value) => write!(f, "bid {}", value),
+            ActionType::StopBidding => write!(f, "stop"),
+            ActionType::Pass(cards) => {
+                write!(f, "pass")?;
+                for card in cards {
+                    write!(f, " {}", card)?;
+                }
+                Ok(())
+            }
+            ActionType::CardPlayed(card) => write!(f, "play {}", card),
+            ActionType::Question(QuestionType::Yours) => write!(f, "ask pair"),
+            ActionType::Question(QuestionType::YourHalf(suit)) => {
+                write!(f, "ask half {}", suit_str(*suit))
+            }
+            ActionType::Answer(AnswerType::YesPair(suit)) => {
+                write!(f, "answer pair {}", suit_str(*suit))
+            }
+            ActionType::Answer(AnswerType::NoPair) => write!(f, "answer nopair"),
+            ActionType::Answer(AnswerType::YesHalf(suit)) => {
+                write!(f, "answer half {}", suit_str(*suit))
+            }
+            ActionType::Answer(AnswerType::NoHalf(suit)) => {
+                write!(f, "answer nohalf {}", suit_str(*suit))
+            }
+            ActionType::AnnounceTrump(suit) => write!(f, "announce {}", suit_str(*suit)),
+            ActionType::UndoRequest => write!(f, "undo"),
+            ActionType::UndoAccept => write!(f, "undo accept"),
+            ActionType::UndoDecline => write!(f, "undo decline"),
+        }
+    }
+}
+
+impl FromStr for ActionType {
+    type Err = Error;
+
+    /// Parses the notation written by `Display`, a bare card like `e-A` is read as `play e-A`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::other(format!("The action '{}' could not be parsed.", s));
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let action_type = match parts.as_slice() {
+            ["start"] => ActionType::Start,
+            ["bid", value] => ActionType::NewBid(value.parse::<i32>().map_err(|_| err())?),
+            ["stop"] => ActionType::StopBidding,
+            ["pass", cards @ ..] => {
+                if cards.len() != 4 {
+                    return Err(err());
+                }
+                let mut cards = cards
+                    .iter()
+                    .map(|c| c.parse::<Card>())
+                    .collect::<Result<Vec<Card>, Error>>()?;
+                // legal passes are always sorted descending
+                cards.sort();
+                cards.reverse();
+                ActionType::Pass(cards)
+            }
+            ["play", card] => ActionType::CardPlayed(card.parse()?),
+            [card] if card.contains('-') => ActionType::CardPlayed(card.parse()?),
+            ["ask", "pair"] => ActionType::Question(QuestionType::Yours),
+            ["ask", "half", suit] => {
+                ActionType::Question(QuestionType::YourHalf(parse_suit_str(suit)?))
+            }
+            ["announce", suit] => ActionType::AnnounceTrump(parse_suit_str(suit)?),
+            ["answer", "pair", suit] => {
+                ActionType::Answer(AnswerType::YesPair(parse_suit_str(suit)?))
+            }
+            ["answer", "nopair"] => ActionType::Answer(AnswerType::NoPair),
+            ["answer", "half", suit] => {
+                ActionType::Answer(AnswerType::YesHalf(parse_suit_str(suit)?))
+            }
+            ["answer", "nohalf", suit] => {
+                ActionType::Answer(AnswerType::NoHalf(parse_suit_str(suit)?))
+            }
+            ["undo"] => ActionType::UndoRequest,
+            ["undo", "accept"] => ActionType::UndoAccept,
+            ["undo", "decline"] => ActionType::UndoDecline,
+            _ => return Err(err()),
+        };
+        Ok(action_type)
+    }
+}
+
+/// Written as seat followed by the action, e.g. `3 bid 155`.
+impl fmt::Display for GameAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.player.0, self.action_type)
+    }
+}
+
+impl FromStr for GameAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (seat, action_type) = s
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| Error::other(format!("The action '{}' has no seat.", s)))?;
+        let seat = match seat.parse::<u8>() {
+            Ok(seat) if seat < 4 => seat,
+            _ => return Err(Error::other(format!("'{}' is not a seat.", seat))),
+        };
+        Ok(GameAction {
+            action_type: action_type.parse()?,
+            player: PlaceAtTable(seat),
+        })
+    }
+}
+
+/// Parses a `;` separated move list like `bid 140; stop; play e-A`.
+pub fn parse_action_list(moves: &str) -> Result<Vec<ActionType>, Error> {
+    moves
+        .split(';')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+pub fn format_action_list(actions: &[ActionType]) -> String {
+    actions
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+impl Game {
+    /**
+     * Finds the legal action for a move in notation, with or without a seat.
+     * Without a seat the first legal action of that type is used,
+     * so `start` is pressed by the lowest seat which has not started yet.
+     */
+    pub fn action_from_notation(&self, notation: &str) -> Result<GameAction, Error> {
+        let starts_with_seat = notation
+            .trim()
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_digit());
+        let candidate = if starts_with_seat {
+            let action: GameAction = notation.parse()?;
+            Some(action).filter(|action| self.legal.is_legal(action))
+        } else {
+            let action_type: ActionType = notation.parse()?;
+            self.legal.iter().find(|a| a.action_type == action_type)
+        };
+        candidate.ok_or_else(|| Error::other(format!("'{}' is not legal right now.", notation)))
+    }
+
+    /// Applies a `;` separated move list, stops at the first illegal or unparsable move.
+    pub fn apply_notation(&self, moves: &str) -> Result<Game, Error> {
+        let mut game = self.clone();
+        for notation in moves.split(';').map(str::trim).filter(|m| !m.is_empty()) {
+            let action = game.action_from_notation(notation)?;
+            game = game
+                .apply_action(action)
+                .map_err(|e| Error::other(format!("{:?} for '{}'", e, notation)))?;
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::gamestate::GamePhase;
+    use crate::game::points::Points;
+
+    #[test]
+    fn test_action_type_round_trip() {
+        let all = vec![
+            ActionType::Start,
+            ActionType::NewBid(140),
+            ActionType::StopBidding,
+            ActionType::Pass(vec![
+                "r-K".parse().unwrap(),
+                "s-7".parse().unwrap(),
+                "e-O".parse().unwrap(),
+                "e-8".parse().unwrap(),
+            ]),
+            ActionType::CardPlayed("e-A".parse().unwrap()),
+            ActionType::Question(QuestionType::Yours),
+            ActionType::Question(QuestionType::YourHalf(Suit::Bells)),
+            ActionType::Answer(AnswerType::YesPair(Suit::Red)),
+            ActionType::Answer(AnswerType::NoPair),
+            ActionType::Answer(AnswerType::YesHalf(Suit::Acorns)),
+            ActionType::Answer(AnswerType::NoHalf(Suit::Green)),
+            ActionType::AnnounceTrump(Suit::Green),
+            ActionType::UndoRequest,
+            ActionType::UndoAccept,
+            ActionType::UndoDecline,
+        ];
+        for action in all.clone() {
+            assert_eq!(action.to_string().parse::<ActionType>().unwrap(), action);
+        }
+        assert_eq!(parse_action_list(&format_action_list(&all)).unwrap(), all);
+    }
+
+    #[test]
+    fn test_parse_notation() {
+        assert_eq!(
+            "pass g-9 g-A e-O e-A".parse::<ActionType>().unwrap(),
+            "pass e-A e-O g-A g-9".parse::<ActionType>().unwrap()
+        );
+        assert_eq!(
+            "e-A".parse::<ActionType>().unwrap(),
+            ActionType::CardPlayed("e-A".parse().unwrap())
+        );
+        assert_eq!(
+            "2 bid 135".parse::<GameAction>().unwrap(),
+            GameAction {
+                action_type: ActionType::NewBid(135),
+                player: PlaceAtTable(2),
+            }
+        );
+        assert!("bid".parse::<ActionType>().is_err());
+        assert!("pass e-A".parse::<ActionType>().is_err());
+        assert!("ask half x".parse::<ActionType>().is_err());
+        assert!("4 stop".parse::<GameAction>().is_err());
+    }
+
+    #[test]
+    fn test_apply_notation() {
+        let names = ["S1", "S2", "S3", "S4"].map(String::from);
+        let game = Game::new(String::from("Notation"), names, None);
+        let game = game
+            .apply_notation("start; start; start; start; bid 140; stop; stop; stop")
+            .unwrap();
+        assert_eq!(game.state.phase, GamePhase::PassingForth);
+        assert_eq!(game.state.value, Points(140));
+        assert!(game.apply_notation("bid 150").is_err());
+    }
+}
@@ -1,19 +1,22 @@
 use std::collections::HashMap;
-use std::io::{Error, ErrorKind};
+use std::fmt;
+use std::io::{BufRead, Error};
 use std::iter::zip;
 
-use serde::Deserialize;
+use serde::de::{SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use strum::IntoEnumIterator;
 
 use crate::game::cards::{Card, Suit, Value};
-use crate::game::gameevent::{ActionType, AnswerType, GameAction, QuestionType};
+use crate::game::gameevent::{effective_events, ActionType, AnswerType, GameAction, QuestionType};
 use crate::game::gameinfo::GameFinishedInfo;
+use crate::game::notation::suit_str;
 use crate::game::player::PlaceAtTable;
 use crate::game::Game;
 
 pub fn parse_card(card: String) -> Result<Card, Error> {
     if card.len() != 3 {
-        return Err(Error::new(ErrorKind::Other, "wrong card format"));
+        return Err(Error::other("wrong card format"));
     }
     let suit_char = card.chars().next().unwrap();
     let value_char = card.chars().last().unwrap();
@@ -39,7 +42,7 @@ pub fn parse_card(card: String) -> Result<Card, Error> {
     }
 
     if value.is_none() || suit.is_none() {
-        return Err(Error::new(ErrorKind::Other, "Wrong card format"));
+        return Err(Error::other("Wrong card format"));
     }
 
     Ok(Card {
@@ -52,10 +55,10 @@ pub fn parse_cards(cards: Vec<String>) -> Vec<Card> {
     cards.into_iter().map(|c| parse_card(c).unwrap()).collect()
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct LegacyGameFormat {
     /// The id of the game found in '_id.$oid'
-    #[serde(rename = "_id")]
+    #[serde(rename = "_id", deserialize_with = "deserialize_id")]
     id: String,
     name: String,
     created: String,
@@ -70,32 +73,52 @@ pub struct LegacyGameFormat {
     tricks: Vec<Vec<String>>,
     #[serde(default)]
     schwarz_game: bool,
+    #[serde(default)]
+    series_id: String,
+    #[serde(default)]
+    passed_cards: Option<LegacyPassedCards>,
+    #[serde(default)]
+    playing_player: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LegacyPassedCards {
+    forth: Vec<String>,
+    back: Vec<String>,
 }
 
 fn parse_action(action: String) -> Result<GameAction, Error> {
-    let err = Err(Error::new(
-        ErrorKind::Other,
-        format!("The action {} could not be parsed.", action),
-    ));
+    let err = Err(Error::other(format!(
+        "The action {} could not be parsed.",
+        action
+    )));
 
     let parts: Vec<&str> = action.split(',').collect();
     if parts.len() != 3 {
         return err;
     }
-    let player_seat = parts[0].parse::<u8>().unwrap();
+    let player_seat = match parts[0].parse::<u8>() {
+        Ok(seat) if seat < 4 => seat,
+        _ => return err,
+    };
     let action_type = parts[1];
     let action_value = parts[2];
 
     let action_type: ActionType = match action_type {
         "PROV" => {
-            let val = action_value.parse::<i32>().unwrap();
+            let Ok(val) = action_value.parse::<i32>() else {
+                return err;
+            };
             if val == 0 {
                 ActionType::StopBidding
             } else {
                 ActionType::NewBid(val)
             }
         }
-        "PRMO" => ActionType::NewBid(action_value.parse::<i32>().unwrap()),
+        "PRMO" => match action_value.parse::<i32>() {
+            Ok(val) => ActionType::NewBid(val),
+            Err(_) => return err,
+        },
         "TRCK" => {
             let card = parse_card(action_value.to_string())?;
             ActionType::CardPlayed(card)
@@ -111,20 +134,25 @@ fn parse_action(action: String) -> Result<GameAction, Error> {
     })
 }
 
-fn parse_pass(actions: Vec<String>) -> GameAction {
-    let player_seat = &actions[0][0..1].parse::<u8>().unwrap();
+fn parse_pass(actions: Vec<String>) -> Result<GameAction, Error> {
+    let err = || Error::other(format!("The pass {:?} could not be parsed.", actions));
+    let player_seat = actions[0]
+        .get(0..1)
+        .and_then(|seat| seat.parse::<u8>().ok())
+        .filter(|seat| *seat < 4)
+        .ok_or_else(err)?;
     let mut cards = vec![];
-    for action in actions {
+    for action in &actions {
         let parts: Vec<&str> = action.split(',').collect();
-        let card = parse_card(parts[2].to_string()).unwrap();
+        let card = parse_card(parts.get(2).ok_or_else(err)?.to_string())?;
         cards.push(card);
     }
     cards.sort();
     cards.reverse();
-    GameAction {
+    Ok(GameAction {
         action_type: ActionType::Pass(cards),
-        player: PlaceAtTable(*player_seat),
-    }
+        player: PlaceAtTable(player_seat),
+    })
 }
 
 fn parse_suit(suit: &str) -> Suit {
@@ -139,12 +167,12 @@ fn parse_suit(suit: &str) -> Suit {
 
 fn parse_ques(action: String) -> ActionType {
     let mut action_type = ActionType::Question(QuestionType::Yours);
-    if &action[7..9] == "my" {
-        let col = parse_suit(&action[9..10]);
+    if action.get(7..9) == Some("my") {
+        let col = parse_suit(action.get(9..10).unwrap_or_default());
         action_type = ActionType::AnnounceTrump(col);
     }
-    if &action[7..9] == "ou" {
-        let col = parse_suit(&action[9..10]);
+    if action.get(7..9) == Some("ou") {
+        let col = parse_suit(action.get(9..10).unwrap_or_default());
         action_type = ActionType::Question(QuestionType::YourHalf(col));
     }
     action_type
@@ -152,64 +180,461 @@ fn parse_ques(action: String) -> ActionType {
 
 fn parse_answ(action: String) -> ActionType {
     let mut action_type = ActionType::Answer(AnswerType::NoPair);
-    if &action[7..9] == "my" {
-        let col = parse_suit(&action[9..10]);
+    if action.get(7..9) == Some("my") {
+        let col = parse_suit(action.get(9..10).unwrap_or_default());
         action_type = ActionType::Answer(AnswerType::YesPair(col));
     }
-    if &action[7..9] == "no" {
-        let col = parse_suit(&action[9..10]);
+    if action.get(7..9) == Some("no") {
+        let col = parse_suit(action.get(9..10).unwrap_or_default());
         action_type = ActionType::Answer(AnswerType::NoHalf(col));
     }
-    if &action[7..9] == "ou" {
-        let col = parse_suit(&action[9..10]);
+    if action.get(7..9) == Some("ou") {
+        let col = parse_suit(action.get(9..10).unwrap_or_default());
         action_type = ActionType::Answer(AnswerType::YesHalf(col));
     }
     action_type
 }
 
+/// Why a legacy game could not be imported.
+#[derive(Debug, Clone, Serialize)]
+pub struct LegacyImportError {
+    /// None if not even the id could be read
+    pub id: Option<String>,
+    /// Index into `actions` of the action which failed
+    pub action_index: Option<usize>,
+    pub reason: String,
+}
+
+impl fmt::Display for LegacyImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "game {}", self.id.as_deref().unwrap_or("without id"))?;
+        if let Some(index) = self.action_index {
+            write!(f, " at action {}", index)?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
 pub fn parse_legacy_format(game_data: LegacyGameFormat) -> Result<GameFinishedInfo, Error> {
-    let names: [String; 4] = game_data.players.clone().try_into().unwrap();
+    import_legacy_game(game_data).map_err(|e| Error::other(e.to_string()))
+}
+
+/// Replays a legacy game without panicking on broken data.
+pub fn import_legacy_game(
+    game_data: LegacyGameFormat,
+) -> Result<GameFinishedInfo, LegacyImportError> {
+    let fail = |action_index: Option<usize>, reason: String| LegacyImportError {
+        id: Some(game_data.id.clone()),
+        action_index,
+        reason,
+    };
+    let names: [String; 4] = game_data
+        .players
+        .clone()
+        .try_into()
+        .map_err(|players| fail(None, format!("expected four players, got {:?}", players)))?;
 
-    let cards = [
-        parse_cards(game_data.cards.get(&names[0]).unwrap().clone()),
-        parse_cards(game_data.cards.get(&names[1]).unwrap().clone()),
-        parse_cards(game_data.cards.get(&names[2]).unwrap().clone()),
-        parse_cards(game_data.cards.get(&names[3]).unwrap().clone()),
-    ];
+    let mut cards: [Vec<Card>; 4] = [vec![], vec![], vec![], vec![]];
+    for (i, name) in names.iter().enumerate() {
+        let players_cards = game_data
+            .cards
+            .get(name)
+            .ok_or_else(|| fail(None, format!("no cards for {}", name)))?;
+        for card in players_cards {
+            cards[i].push(parse_card(card.clone()).map_err(|e| fail(None, e.to_string()))?);
+        }
+    }
 
-    let mut game_replay = Game::new(game_data.name, names, Some(cards));
+    let mut game_replay = Game::new(game_data.name.clone(), names, Some(cards));
 
     for _ in 0..4 {
-        game_replay.apply_action_mut(game_replay.legal_actions[0].clone());
+        game_replay = game_replay
+            .apply_action(game_replay.legal_actions()[0].clone())
+            .map_err(|e| fail(None, format!("{:?} while starting", e)))?;
     }
 
     let mut pass_collect = vec![];
-    for action in game_data.actions {
-        //println!("{:?}", game_replay.legal_actions);
-        //println!("{}", action.clone());
+    for (index, action) in game_data.actions.iter().enumerate() {
         if pass_collect.len() == 4 {
-            let pass_action = parse_pass(pass_collect.clone());
-            //println!("{:?}", pass_action);
-            game_replay.apply_action_mut(pass_action);
+            let pass_action =
+                parse_pass(pass_collect.clone()).map_err(|e| fail(Some(index), e.to_string()))?;
+            game_replay = game_replay
+                .apply_action(pass_action.clone())
+                .map_err(|e| fail(Some(index - 1), format!("{:?}: {}", e, pass_action)))?;
             pass_collect = vec![];
         }
-        if &action[2..6] == "PASS" || &action[2..6] == "PBCK" {
+        if matches!(action.get(2..6), Some("PASS") | Some("PBCK")) {
             pass_collect.push(action.clone());
             continue;
         }
-        let new_action = parse_action(action.clone())?;
-        //println!("{:?}", new_action);
+        let new_action =
+            parse_action(action.clone()).map_err(|e| fail(Some(index), e.to_string()))?;
         if new_action.action_type == ActionType::NewBid(0) {
             continue;
         }
-        game_replay.apply_action_mut(new_action);
+        game_replay = game_replay
+            .apply_action(new_action.clone())
+            .map_err(|e| fail(Some(index), format!("{:?}: {}", e, new_action)))?;
+    }
+    if !game_replay.ended() {
+        return Err(fail(
+            None,
+            format!("game did not end, still in {:?}", game_replay.state.phase),
+        ));
     }
-    //print!("{:#?}", game_replay.state.phase);
     let mut game_db = GameFinishedInfo::from(game_replay);
-    game_db.set_times(game_data.created, game_data.started, game_data.finished);
+    game_db.set_times(
+        game_data.created.clone(),
+        game_data.started.clone(),
+        game_data.finished.clone(),
+    );
     Ok(game_db)
 }
 
+impl LegacyGameFormat {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// A recorded outcome of the Python engine which the replay does not agree with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum LegacyDiscrepancy {
+    GameValue {
+        recorded: i32,
+        replayed: i32,
+    },
+    TrickCount {
+        recorded: usize,
+        replayed: usize,
+    },
+    TrickCards {
+        trick: usize,
+        recorded: Vec<String>,
+        replayed: Vec<String>,
+    },
+    TrickWinner {
+        trick: usize,
+        recorded: u8,
+        replayed: u8,
+    },
+    PlayerPoints {
+        player: String,
+        recorded: i32,
+        replayed: i32,
+    },
+    AnnouncedSuits {
+        player: String,
+        recorded: Vec<String>,
+        replayed: Vec<String>,
+    },
+    Schwarz {
+        recorded: bool,
+        replayed: bool,
+    },
+}
+
+/**
+ * Compares the replayed game with everything the Python engine recorded.
+ * Trick winners are only recorded implicitly as the player leading the next trick,
+ * so the winner of the last trick is checked through the points.
+ */
+pub fn validate_legacy_import(
+    legacy: &LegacyGameFormat,
+    replayed: &GameFinishedInfo,
+) -> Vec<LegacyDiscrepancy> {
+    let mut discrepancies = vec![];
+    if legacy.game_value != replayed.game_value.0 {
+        discrepancies.push(LegacyDiscrepancy::GameValue {
+            recorded: legacy.game_value,
+            replayed: replayed.game_value.0,
+        });
+    }
+
+    if legacy.tricks.len() != replayed.tricks.len() {
+        discrepancies.push(LegacyDiscrepancy::TrickCount {
+            recorded: legacy.tricks.len(),
+            replayed: replayed.tricks.len(),
+        });
+    }
+    for (i, (recorded, trick)) in zip(&legacy.tricks, &replayed.tricks).enumerate() {
+        let cards: Vec<String> = trick.cards.iter().map(|c| c.to_string()).collect();
+        if *recorded != cards {
+            discrepancies.push(LegacyDiscrepancy::TrickCards {
+                trick: i,
+                recorded: recorded.clone(),
+                replayed: cards,
+            });
+        }
+    }
+    let leaders: Vec<u8> = legacy
+        .actions
+        .iter()
+        .filter(|action| action.get(2..6) == Some("TRCK"))
+        .filter_map(|action| action.get(0..1)?.parse::<u8>().ok())
+        .step_by(4)
+        .collect();
+    for (i, trick) in replayed.tricks.iter().enumerate() {
+        if let Some(leader) = leaders.get(i + 1) {
+            if *leader != trick.winner.0 {
+                discrepancies.push(LegacyDiscrepancy::TrickWinner {
+                    trick: i,
+                    recorded: *leader,
+                    replayed: trick.winner.0,
+                });
+            }
+        }
+    }
+
+    let players_points = replayed.players_points();
+    let players_announced = replayed.players_announced();
+    for (i, name) in replayed.info.player_names.iter().enumerate() {
+        let recorded = legacy.players_points.get(name).copied().unwrap_or_default();
+        if recorded != players_points[i].0 {
+            discrepancies.push(LegacyDiscrepancy::PlayerPoints {
+                player: name.clone(),
+                recorded,
+                replayed: players_points[i].0,
+            });
+        }
+        let mut recorded = legacy.players_sup.get(name).cloned().unwrap_or_default();
+        let mut announced: Vec<String> = players_announced[i]
+            .iter()
+            .map(|suit| suit_str(*suit).to_string())
+            .collect();
+        recorded.sort();
+        announced.sort();
+        if recorded != announced {
+            discrepancies.push(LegacyDiscrepancy::AnnouncedSuits {
+                player: name.clone(),
+                recorded,
+                replayed: announced,
+            });
+        }
+    }
+
+    if legacy.schwarz_game != replayed.schwarz_game {
+        discrepancies.push(LegacyDiscrepancy::Schwarz {
+            recorded: legacy.schwarz_game,
+            replayed: replayed.schwarz_game,
+        });
+    }
+    discrepancies
+}
+
+/// Ids are plain strings or Mongo exports like `{"$oid": "..."}`.
+fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LegacyId {
+        Plain(String),
+        Mongo {
+            #[serde(rename = "$oid")]
+            oid: String,
+        },
+    }
+    Ok(match LegacyId::deserialize(deserializer)? {
+        LegacyId::Plain(id) => id,
+        LegacyId::Mongo { oid } => oid,
+    })
+}
+
+fn legacy_id(value: &serde_json::Value) -> Option<String> {
+    let id = value.get("_id")?;
+    id.as_str()
+        .or_else(|| id.get("$oid").and_then(|oid| oid.as_str()))
+        .map(String::from)
+}
+
+struct StreamVisitor<F> {
+    callback: F,
+}
+
+impl<'de, F: FnMut(serde_json::Value)> Visitor<'de> for StreamVisitor<F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a list of legacy games")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(value) = seq.next_element::<serde_json::Value>()? {
+            (self.callback)(value);
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Reads legacy games one by one, either from a top level JSON list or from newline-delimited JSON.
+ * The callback gets the index of the game in the input and the game or why it could not be read.
+ * Only broken JSON syntax aborts the whole stream.
+ */
+pub fn stream_legacy_games<R: BufRead>(
+    mut reader: R,
+    mut callback: impl FnMut(usize, Result<LegacyGameFormat, LegacyImportError>),
+) -> Result<(), Error> {
+    let mut index = 0;
+    let mut handle = |value: serde_json::Value| {
+        let id = legacy_id(&value);
+        let game =
+            serde_json::from_value::<LegacyGameFormat>(value).map_err(|e| LegacyImportError {
+                id,
+                action_index: None,
+                reason: e.to_string(),
+            });
+        callback(index, game);
+        index += 1;
+    };
+
+    // skip whitespace to see whether this is a list
+    let is_list = loop {
+        let buffer = reader.fill_buf()?;
+        match buffer.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(position) => {
+                let is_list = buffer[position] == b'[';
+                reader.consume(position);
+                break is_list;
+            }
+            None if buffer.is_empty() => return Ok(()),
+            None => {
+                let length = buffer.len();
+                reader.consume(length);
+            }
+        }
+    };
+
+    if is_list {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        deserializer
+            .deserialize_seq(StreamVisitor { callback: handle })
+            .map_err(Error::other)?;
+    } else {
+        for value in serde_json::Deserializer::from_reader(reader).into_iter() {
+            handle(value.map_err(Error::other)?);
+        }
+    }
+    Ok(())
+}
+
+fn legacy_action(action: &GameAction) -> Vec<String> {
+    let seat = action.player.0;
+    let (kind, values): (&str, Vec<String>) = match &action.action_type {
+        ActionType::NewBid(value) => ("PROV", vec![value.to_string()]),
+        ActionType::StopBidding => ("PROV", vec![String::from("0")]),
+        ActionType::CardPlayed(card) => ("TRCK", vec![card.to_string()]),
+        ActionType::AnnounceTrump(suit) => ("QUES", vec![format!("my{}", suit_str(*suit))]),
+        ActionType::Question(QuestionType::Yours) => ("QUES", vec![String::from("you")]),
+        ActionType::Question(QuestionType::YourHalf(suit)) => {
+            ("QUES", vec![format!("ou{}", suit_str(*suit))])
+        }
+        ActionType::Answer(AnswerType::NoPair) => ("ANSW", vec![String::from("nmy")]),
+        ActionType::Answer(AnswerType::YesPair(suit)) => {
+            ("ANSW", vec![format!("my{}", suit_str(*suit))])
+        }
+        ActionType::Answer(AnswerType::NoHalf(suit)) => {
+            ("ANSW", vec![format!("no{}", suit_str(*suit))])
+        }
+        ActionType::Answer(AnswerType::YesHalf(suit)) => {
+            ("ANSW", vec![format!("ou{}", suit_str(*suit))])
+        }
+        // passing and raising depend on the phase and are handled by the caller
+        _ => ("", vec![]),
+    };
+    values
+        .into_iter()
+        .map(|value| format!("{},{},{}", seat, kind, value))
+        .collect()
+}
+
+/**
+ * Inverse of `parse_legacy_format` for the old statistics pages.
+ * Undone actions are left out, since the legacy format has no undo.
+ */
+pub fn to_legacy_format(game: &GameFinishedInfo, id: String) -> LegacyGameFormat {
+    let names = game.info.player_names.clone();
+    let cards_strings =
+        |cards: &[Card]| -> Vec<String> { cards.iter().map(|c| c.to_string()).collect() };
+
+    let mut actions = vec![];
+    let mut passes = 0;
+    let mut raising = false;
+    for event in effective_events(&game.all_events) {
+        let action = &event.last_action;
+        match &action.action_type {
+            ActionType::Pass(cards) => {
+                let kind = if passes == 0 { "PASS" } else { "PBCK" };
+                for card in cards {
+                    actions.push(format!("{},{},{}", action.player.0, kind, card));
+                }
+                passes += 1;
+                raising = passes == 2;
+            }
+            ActionType::NewBid(value) if raising => {
+                actions.push(format!("{},PRMO,{}", action.player.0, value));
+                raising = false;
+            }
+            ActionType::CardPlayed(_) if raising => {
+                actions.push(format!("{},PRMO,0", action.player.0));
+                actions.extend(legacy_action(action));
+                raising = false;
+            }
+            _ => actions.extend(legacy_action(action)),
+        }
+    }
+
+    let players_points = game.players_points();
+    let players_announced = game.players_announced();
+    let mut cards = HashMap::new();
+    let mut players_sup = HashMap::new();
+    let mut points = HashMap::new();
+    for (i, name) in names.iter().enumerate() {
+        cards.insert(
+            name.clone(),
+            cards_strings(&game.info.player_start_cards[i]),
+        );
+        players_sup.insert(
+            name.clone(),
+            players_announced[i]
+                .iter()
+                .map(|suit| suit_str(*suit).to_string())
+                .collect(),
+        );
+        points.insert(name.clone(), players_points[i].0);
+    }
+
+    LegacyGameFormat {
+        id,
+        name: game.info.name.clone(),
+        created: game.info.create_time.clone(),
+        started: game.info.start_time.clone().unwrap_or_default(),
+        finished: game.info.end_time.clone().unwrap_or_default(),
+        players: names.to_vec(),
+        cards,
+        game_value: game.game_value.0,
+        actions,
+        players_sup,
+        players_points: points,
+        tricks: game
+            .tricks
+            .iter()
+            .map(|trick| cards_strings(&trick.cards))
+            .collect(),
+        schwarz_game: game.schwarz_game,
+        series_id: String::new(),
+        passed_cards: game
+            .passed_cards
+            .as_ref()
+            .map(|(forth, back)| LegacyPassedCards {
+                forth: cards_strings(forth),
+                back: cards_strings(back),
+            }),
+        playing_player: game
+            .playing_player()
+            .map(|place| names[place.0 as usize].clone()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,9 +665,8 @@ mod tests {
         );
     }
 
-    #[test]
-    pub fn test_parse_python_game() {
-        let input: LegacyGameFormat = serde_json::from_str(
+    fn legacy_fixture() -> LegacyGameFormat {
+        serde_json::from_str(
             r#"
         {
            "_id": "ffffffffe1f3816d1dad798f",
@@ -457,8 +881,159 @@ mod tests {
         }
       "#,
         )
-        .unwrap();
+        .unwrap()
+    }
+
+    #[test]
+    pub fn test_parse_python_game() {
+        let input = legacy_fixture();
         let result = parse_legacy_format(input.clone()).unwrap();
         assert_eq!(result.game_value, Points(input.game_value));
     }
+
+    #[test]
+    pub fn test_import_reports_failing_action() {
+        let mut broken = legacy_fixture();
+        broken.actions[17] = String::from("0,TRCK,r-A");
+        let err = import_legacy_game(broken).unwrap_err();
+        assert_eq!(err.id, Some(String::from("ffffffffe1f3816d1dad798f")));
+        assert_eq!(err.action_index, Some(17));
+
+        let mut short = legacy_fixture();
+        short.actions[3] = String::from("3");
+        assert_eq!(import_legacy_game(short).unwrap_err().action_index, Some(3));
+    }
+
+    #[test]
+    pub fn test_stream_legacy_games() {
+        let game = serde_json::to_string(&legacy_fixture()).unwrap();
+        let mongo_id = game.replace(
+            "\"_id\":\"ffffffffe1f3816d1dad798f\"",
+            "\"_id\":{\"$oid\":\"abc\"}",
+        );
+        let broken = r#"{"_id": "broken", "name": 3}"#;
+        let list = format!("  [{}, {}, {}]", game, broken, mongo_id);
+        let lines = format!("{}\n{}\n{}\n", game, broken, mongo_id);
+        for input in [list, lines] {
+            let mut results = vec![];
+            stream_legacy_games(input.as_bytes(), |index, game| results.push((index, game)))
+                .unwrap();
+            assert_eq!(results.len(), 3);
+            assert!(results[0].1.is_ok());
+            let err = results[1].1.as_ref().unwrap_err();
+            assert_eq!(err.id, Some(String::from("broken")));
+            assert_eq!(results[2].0, 2);
+            assert_eq!(results[2].1.as_ref().unwrap().id, "abc");
+        }
+    }
+
+    #[test]
+    pub fn test_validate_python_game() {
+        let input = legacy_fixture();
+        let result = import_legacy_game(input.clone()).unwrap();
+        assert_eq!(validate_legacy_import(&input, &result), vec![]);
+
+        let mut tampered = input.clone();
+        tampered.players_points.insert(String::from("Player B"), 99);
+        tampered
+            .players_sup
+            .insert(String::from("Player A"), vec![String::from("g")]);
+        tampered.tricks[1].swap(0, 1);
+        tampered.schwarz_game = false;
+        assert_eq!(
+            validate_legacy_import(&tampered, &result),
+            vec![
+                LegacyDiscrepancy::TrickCards {
+                    trick: 1,
+                    recorded: vec!["g-6", "g-A", "s-7", "g-U"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                    replayed: vec!["g-A", "g-6", "s-7", "g-U"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                },
+                LegacyDiscrepancy::AnnouncedSuits {
+                    player: String::from("Player A"),
+                    recorded: vec![String::from("g")],
+                    replayed: vec![],
+                },
+                LegacyDiscrepancy::PlayerPoints {
+                    player: String::from("Player B"),
+                    recorded: 99,
+                    replayed: 199,
+                },
+                LegacyDiscrepancy::Schwarz {
+                    recorded: false,
+                    replayed: true,
+                },
+            ]
+        );
+
+        let mut wrong_leader = input;
+        // the second trick is led by seat 3 who won the first
+        wrong_leader.actions[20] = String::from("2,TRCK,g-A");
+        assert!(validate_legacy_import(&wrong_leader, &result).contains(
+            &LegacyDiscrepancy::TrickWinner {
+                trick: 0,
+                recorded: 2,
+                replayed: 3,
+            }
+        ));
+    }
+
+    fn without_pass_order(actions: &[String]) -> Vec<String> {
+        // the order of passed cards is not part of the game
+        let (mut forth, mut back, other): (Vec<String>, Vec<String>, Vec<String>) = (
+            actions
+                .iter()
+                .filter(|a| a.contains("PASS"))
+                .cloned()
+                .collect(),
+            actions
+                .iter()
+                .filter(|a| a.contains("PBCK"))
+                .cloned()
+                .collect(),
+            actions
+                .iter()
+                .filter(|a| !a.contains("PASS") && !a.contains("PBCK"))
+                .cloned()
+                .collect(),
+        );
+        forth.sort();
+        back.sort();
+        [forth, back, other].concat()
+    }
+
+    #[test]
+    pub fn test_export_python_game() {
+        let input = legacy_fixture();
+        let finished = parse_legacy_format(input.clone()).unwrap();
+        let exported = to_legacy_format(&finished, input.id.clone());
+
+        assert_eq!(exported.id, input.id);
+        assert_eq!(exported.name, input.name);
+        assert_eq!(exported.created, input.created);
+        assert_eq!(exported.started, input.started);
+        assert_eq!(exported.finished, input.finished);
+        assert_eq!(exported.players, input.players);
+        assert_eq!(exported.cards, input.cards);
+        assert_eq!(exported.game_value, input.game_value);
+        assert_eq!(
+            without_pass_order(&exported.actions),
+            without_pass_order(&input.actions)
+        );
+        assert_eq!(exported.players_points, input.players_points);
+        assert_eq!(exported.players_sup, input.players_sup);
+        assert_eq!(exported.tricks, input.tricks);
+        assert_eq!(exported.schwarz_game, input.schwarz_game);
+        assert_eq!(exported.playing_player, input.playing_player);
+
+        let reparsed = parse_legacy_format(exported).unwrap();
+        assert_eq!(reparsed.game_value, finished.game_value);
+        assert_eq!(reparsed.won, finished.won);
+        assert_eq!(reparsed.players_points(), finished.players_points());
+    }
 }
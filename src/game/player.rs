@@ -1,12 +1,13 @@
 use std::fmt::Debug;
 
-use rand::rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use serde::Serialize;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
 use crate::game::cards::{get_all_cards, Card};
 
-#[derive(Clone, PartialEq, Eq, Serialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PlaceAtTable(pub u8);
 
 impl PlaceAtTable {
@@ -32,7 +33,7 @@ impl Debug for PlaceAtTable {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Player {
     pub name: String,
     pub partner: PlaceAtTable,
@@ -45,14 +46,6 @@ pub struct Player {
     pub bidding: bool,
 }
 
-impl PartialEq for Player {
-    fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
-    }
-}
-
-impl Eq for Player {}
-
 impl Debug for Player {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Player")
@@ -77,7 +70,7 @@ impl Player {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum PlayerTrumpPossibilities {
     Own,
     Yours,
@@ -101,21 +94,36 @@ fn create_player(name: String, cards: Vec<Card>, place: u8) -> Player {
     }
 }
 
-pub fn create_players(names: [String; 4], cards: Option<[Vec<Card>; 4]>) -> [Player; 4] {
-    let players_cards = cards.unwrap_or_else(|| {
-        //random shuffled cads
-        let mut deck = get_all_cards();
-        deck.shuffle(&mut rng());
-        let mut cards: [Vec<Card>; 4] = [vec![], vec![], vec![], vec![]];
-        for i in 0..4 {
-            let mut one_players_cards = vec![];
-            for c in 0..9 {
-                one_players_cards.push(deck.get(i * 9 + c).unwrap().clone());
-            }
-            cards[i] = one_players_cards;
+/// Shuffles all cards and deals nine to every player.
+pub fn deal_cards<R: Rng + ?Sized>(rng: &mut R) -> [Vec<Card>; 4] {
+    let mut deck = get_all_cards();
+    deck.shuffle(rng);
+    let mut cards: [Vec<Card>; 4] = [vec![], vec![], vec![], vec![]];
+    for (i, one_players_cards) in cards.iter_mut().enumerate() {
+        for c in 0..9 {
+            one_players_cards.push(deck.get(i * 9 + c).unwrap().clone());
         }
-        cards
-    });
+    }
+    cards
+}
+
+/// The same seed always results in the same deal.
+pub fn seeded_cards(seed: u64) -> [Vec<Card>; 4] {
+    deal_cards(&mut StdRng::seed_from_u64(seed))
+}
+
+/// A random deal. There is no thread rng on wasm32, games there need a seed or given cards.
+fn random_cards() -> [Vec<Card>; 4] {
+    #[cfg(not(target_arch = "wasm32"))]
+    return deal_cards(&mut rand::rng());
+    #[cfg(target_arch = "wasm32")]
+    panic!(
+        "on wasm32 games are dealt from a seed, e.g. from crypto.getRandomValues, or given cards"
+    );
+}
+
+pub fn create_players(names: [String; 4], cards: Option<[Vec<Card>; 4]>) -> [Player; 4] {
+    let players_cards = cards.unwrap_or_else(random_cards);
 
     let p0 = create_player(names[0].clone(), players_cards[0].clone(), 0);
     let p1 = create_player(names[1].clone(), players_cards[1].clone(), 1);
@@ -0,0 +1,356 @@
+use std::fmt;
+use std::io::Error;
+use std::str::FromStr;
+
+use crate::game::cards::Card;
+use crate::game::errors::GameError;
+use crate::game::gameevent::GameAction;
+use crate::game::gameinfo::GameFinishedInfo;
+use crate::game::player::seeded_cards;
+use crate::game::Game;
+
+/// The rule set implemented by this crate.
+pub const RULES: &str = "marjapussi.de";
+
+/// Result tag of games which have not ended.
+pub const UNFINISHED: &str = "*";
+
+/**
+ * Portable text record of a game, similar to PGN:
+ *
+ * ```text
+ * [Event "Friday Night"]
+ * [Date "2023-04-15 22:45:13"]
+ * [Seat0 "Player A"]
+ * ...
+ * [Rules "marjapussi.de"]
+ * [Result "1+3 won 155"]
+ * [Deal0 "r-6 e-Z e-K e-7 g-K g-O g-8 g-7 g-6"]
+ * ...
+ *
+ * 1. 0 start
+ * 2. 1 start {comments go in braces, \} and newlines are escaped}
+ * ```
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    pub event: String,
+    pub date: Option<String>,
+    pub seats: [String; 4],
+    pub seed: Option<u64>,
+    pub rules: String,
+    pub result: String,
+    pub deal: [Vec<Card>; 4],
+    pub moves: Vec<RecordedMove>,
+    /// Unknown tags are kept so that records survive a round trip.
+    pub extra_tags: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedMove {
+    pub action: GameAction,
+    pub comment: Option<String>,
+}
+
+impl GameRecord {
+    pub fn from_game(game: &Game) -> Self {
+        GameRecord {
+            event: game.info.name.clone(),
+            date: Some(game.info.create_time.clone()),
+            seats: game.info.player_names.clone(),
+            seed: game.info.seed,
+            rules: RULES.to_string(),
+            result: result_tag(game),
+            deal: game.info.player_start_cards.clone(),
+            moves: game
+                .all_events
+                .iter()
+                .map(|event| RecordedMove {
+                    action: event.last_action.clone(),
+                    comment: None,
+                })
+                .collect(),
+            extra_tags: vec![],
+        }
+    }
+
+    /// Adds a comment to the move with the given index, replaces an existing one.
+    pub fn annotate(&mut self, move_index: usize, comment: String) {
+        if let Some(recorded) = self.moves.get_mut(move_index) {
+            recorded.comment = Some(comment);
+        }
+    }
+
+    /// Replays all moves on the recorded deal.
+    pub fn to_game(&self) -> Result<Game, GameError> {
+        let mut game = Game::new(
+            self.event.clone(),
+            self.seats.clone(),
+            Some(self.deal.clone()),
+        );
+        game.info.seed = self.seed;
+        if let Some(date) = &self.date {
+            game.info.create_time.clone_from(date);
+        }
+        for recorded in &self.moves {
+            game = game.apply_action(recorded.action.clone())?;
+        }
+        Ok(game)
+    }
+}
+
+fn result_tag(game: &Game) -> String {
+    if !game.ended() {
+        return UNFINISHED.to_string();
+    }
+    let finished = GameFinishedInfo::from(game.clone());
+    match (finished.playing_party, finished.won) {
+        (Some(party), Some(won)) => format!(
+            "{}+{} {} {}",
+            party.0,
+            party.partner().0,
+            if won { "won" } else { "lost" },
+            finished.game_value.0
+        ),
+        _ => String::from("nobody played"),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some(escaped) => unescaped.push(escaped),
+                None => {}
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+fn format_cards(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut tags: Vec<(String, String)> = vec![(String::from("Event"), self.event.clone())];
+        if let Some(date) = &self.date {
+            tags.push((String::from("Date"), date.clone()));
+        }
+        for (i, seat) in self.seats.iter().enumerate() {
+            tags.push((format!("Seat{}", i), seat.clone()));
+        }
+        if let Some(seed) = self.seed {
+            tags.push((String::from("Seed"), seed.to_string()));
+        }
+        tags.push((String::from("Rules"), self.rules.clone()));
+        tags.push((String::from("Result"), self.result.clone()));
+        for (i, cards) in self.deal.iter().enumerate() {
+            tags.push((format!("Deal{}", i), format_cards(cards)));
+        }
+        tags.extend(self.extra_tags.clone());
+
+        for (key, value) in tags {
+            writeln!(f, "[{} \"{}\"]", key, escape(&value))?;
+        }
+        writeln!(f)?;
+        for (i, recorded) in self.moves.iter().enumerate() {
+            write!(f, "{}. {}", i + 1, recorded.action)?;
+            if let Some(comment) = &recorded.comment {
+                write!(f, " {{{}}}", escape(comment).replace('}', "\\}"))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), Error> {
+    let err = || Error::other(format!("The tag {} could not be parsed.", line));
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(err)?;
+    let (key, value) = inner.split_once(' ').ok_or_else(err)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(err)?;
+    Ok((key.to_string(), unescape(value)))
+}
+
+fn parse_move(line: &str) -> Result<RecordedMove, Error> {
+    let err = || Error::other(format!("The move {} could not be parsed.", line));
+    let (_number, rest) = line.split_once(". ").ok_or_else(err)?;
+    let (action, comment) = match rest.split_once('{') {
+        Some((action, comment)) => (
+            action,
+            Some(unescape(comment.strip_suffix('}').ok_or_else(err)?)),
+        ),
+        None => (rest, None),
+    };
+    Ok(RecordedMove {
+        action: action.parse()?,
+        comment,
+    })
+}
+
+impl FromStr for GameRecord {
+    type Err = Error;
+
+    /// Deal tags may be left out if a seed is given.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut event = None;
+        let mut date = None;
+        let mut seats: [Option<String>; 4] = [None, None, None, None];
+        let mut seed = None;
+        let mut rules = RULES.to_string();
+        let mut result = UNFINISHED.to_string();
+        let mut deal: [Option<Vec<Card>>; 4] = [None, None, None, None];
+        let mut moves = vec![];
+        let mut extra_tags = vec![];
+
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if !line.starts_with('[') {
+                moves.push(parse_move(line)?);
+                continue;
+            }
+            let (key, value) = parse_tag(line)?;
+            let seat = key
+                .chars()
+                .last()
+                .and_then(|c| c.to_digit(10))
+                .filter(|d| *d < 4)
+                .map(|d| d as usize);
+            match (key.as_str(), seat) {
+                ("Event", _) => event = Some(value),
+                ("Date", _) => date = Some(value),
+                ("Seed", _) => {
+                    seed = Some(
+                        value
+                            .parse::<u64>()
+                            .map_err(|_| Error::other(format!("'{}' is no seed.", value)))?,
+                    )
+                }
+                ("Rules", _) => rules = value,
+                ("Result", _) => result = value,
+                (k, Some(i)) if k.starts_with("Seat") && k.len() == 5 => seats[i] = Some(value),
+                (k, Some(i)) if k.starts_with("Deal") && k.len() == 5 => {
+                    deal[i] = Some(value.split_whitespace().map(str::parse).collect::<Result<
+                        Vec<Card>,
+                        Error,
+                    >>(
+                    )?)
+                }
+                _ => extra_tags.push((key, value)),
+            }
+        }
+
+        let seats = seats
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| s.ok_or_else(|| Error::other(format!("Seat{} is missing.", i))))
+            .collect::<Result<Vec<String>, Error>>()?
+            .try_into()
+            .unwrap();
+        let deal = match (deal, seed) {
+            ([Some(d0), Some(d1), Some(d2), Some(d3)], _) => [d0, d1, d2, d3],
+            (_, Some(seed)) => seeded_cards(seed),
+            _ => return Err(Error::other("The deal is missing.")),
+        };
+        Ok(GameRecord {
+            event: event.unwrap_or_default(),
+            date,
+            seats,
+            seed,
+            rules,
+            result,
+            deal,
+            moves,
+            extra_tags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::gamestate::GamePhase;
+    use rand::prelude::IndexedRandom;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn helper_random_game(seed: u64) -> Game {
+        let names = ["Alice", "Bob \"B\"", "Carol", "Dave"].map(String::from);
+        let mut game = Game::new_seeded(String::from("Record"), names, seed);
+        let mut rng = StdRng::seed_from_u64(seed);
+        while game.state.phase != GamePhase::Ended {
+            let action = game.legal_actions().choose(&mut rng).unwrap().clone();
+            game = game.apply_action(action).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let game = helper_random_game(7);
+        let mut record = GameRecord::from_game(&game);
+        record.annotate(4, String::from("opening bid"));
+        let text = record.to_string();
+        let parsed: GameRecord = text.parse().unwrap();
+        assert_eq!(parsed, record);
+
+        let replayed = parsed.to_game().unwrap();
+        assert!(replayed.ended());
+        assert_eq!(replayed.state.value, game.state.value);
+        assert_eq!(GameRecord::from_game(&replayed).result, record.result);
+    }
+
+    #[test]
+    fn test_record_comments_round_trip() {
+        let game = helper_random_game(3);
+        let mut record = GameRecord::from_game(&game);
+        record.annotate(4, String::from("a {braced} bid}"));
+        record.annotate(5, String::from("first line\nsecond line\r\n"));
+        record.annotate(6, String::from(" \\ spaced \"quoted\" \\"));
+        let text = record.to_string();
+        assert_eq!(text.lines().count(), 9 + 4 + 1 + record.moves.len());
+        let parsed: GameRecord = text.parse().unwrap();
+        assert_eq!(parsed, record);
+    }
+
+    #[test]
+    fn test_record_deal_from_seed() {
+        let text = "[Event \"Seeded\"]\n[Seat0 \"A\"]\n[Seat1 \"B\"]\n[Seat2 \"C\"]\n\
+            [Seat3 \"D\"]\n[Seed \"3\"]\n[Annotator \"Coach\"]\n\n1. 0 start\n2. 2 start\n";
+        let record: GameRecord = text.parse().unwrap();
+        assert_eq!(record.deal, seeded_cards(3));
+        assert_eq!(
+            record.extra_tags,
+            vec![(String::from("Annotator"), String::from("Coach"))]
+        );
+        let game = record.to_game().unwrap();
+        assert_eq!(game.state.players_started.len(), 2);
+        assert_eq!(record.result, UNFINISHED);
+    }
+}
@@ -0,0 +1,397 @@
+use strum::IntoEnumIterator;
+
+use crate::game::cards::{Card, Suit, Value};
+use crate::game::gamestate::GamePhase;
+use crate::game::points::points_card;
+use crate::game::zobrist::{fingerprint, suits_mask, KEYS};
+use crate::game::Game;
+
+/// Set of cards, bit `suit * 9 + value` for every card.
+pub type CardMask = u64;
+
+const SUIT_MASK: CardMask = 0x1ff;
+
+pub fn card_index(card: &Card) -> u8 {
+    card.suit as u8 * 9 + card.value as u8
+}
+
+pub fn card_from_index(index: u8) -> Card {
+    Card {
+        suit: Suit::iter().nth((index / 9) as usize).unwrap(),
+        value: Value::iter().nth((index % 9) as usize).unwrap(),
+    }
+}
+
+pub fn cards_mask<'a>(cards: impl IntoIterator<Item = &'a Card>) -> CardMask {
+    cards
+        .into_iter()
+        .fold(0, |mask, card| mask | 1 << card_index(card))
+}
+
+/// Cards of the mask, lowest first.
+pub fn mask_cards(mask: CardMask) -> impl Iterator<Item = u8> {
+    (0..36).filter(move |index| mask & 1 << index != 0)
+}
+
+fn suit_of(index: u8) -> u8 {
+    index / 9
+}
+
+fn suit_mask(suit: u8) -> CardMask {
+    SUIT_MASK << (suit * 9)
+}
+
+/// Whether the card beats the current high card of a trick, see `cards::is_higher_card`.
+fn beats(card: u8, high: u8, trump: Option<u8>) -> bool {
+    let same_suit_higher = suit_of(card) == suit_of(high) && card > high;
+    match trump {
+        Some(trump) => (suit_of(card) == trump && suit_of(high) != trump) || same_suit_higher,
+        None => same_suit_higher,
+    }
+}
+
+/// Position of the winning card in a (partial) trick.
+pub fn trick_winner(trick: &[u8], trump: Option<Suit>) -> usize {
+    let trump = trump.map(|suit| suit as u8);
+    let mut winner = 0;
+    for (position, card) in trick.iter().enumerate().skip(1) {
+        if beats(*card, trick[winner], trump) {
+            winner = position;
+        }
+    }
+    winner
+}
+
+/// Points of a finished trick, the last one is worth 20 more.
+pub fn trick_points(trick: &[u8], last: bool) -> i32 {
+    let points: i32 = trick
+        .iter()
+        .map(|index| points_card(card_from_index(*index)).0)
+        .sum();
+    points + if last { 20 } else { 0 }
+}
+
+/**
+ * Cards of the hand which may be played on the trick, `cards::allowed_cards` uses these rules.
+ * In the first trick an ace of the led suit has to be played,
+ * otherwise a higher card of the led suit, any card of the suit, a higher card or a trump.
+ */
+pub fn allowed_mask(
+    hand: CardMask,
+    trick: &[u8],
+    trump: Option<Suit>,
+    first_trick: bool,
+) -> CardMask {
+    if trick.is_empty() {
+        if !first_trick {
+            return hand;
+        }
+        let aces = mask_cards(hand)
+            .filter(|index| index % 9 == Value::Ace as u8)
+            .fold(0, |mask, index| mask | 1 << index);
+        let green = hand & suit_mask(Suit::Green as u8);
+        return [aces, green, hand]
+            .into_iter()
+            .find(|m| *m != 0)
+            .unwrap_or(0);
+    }
+    let trick_suit = suit_of(trick[0]);
+    let high = trick[trick_winner(trick, trump)];
+    if first_trick {
+        let ace = hand & 1 << (trick_suit * 9 + Value::Ace as u8);
+        if ace != 0 {
+            return ace;
+        }
+    }
+    let trump_index = trump.map(|suit| suit as u8);
+    // the card has to take the trick and rank above the high card
+    let higher = mask_cards(hand)
+        .filter(|card| beats(*card, high, trump_index) && *card > high)
+        .fold(0, |mask, index| mask | 1 << index);
+    let same_suit = hand & suit_mask(trick_suit);
+    let trumps = trump_index.map_or(0, |suit| hand & suit_mask(suit));
+    [higher & same_suit, same_suit, higher, trumps, hand]
+        .into_iter()
+        .find(|m| *m != 0)
+        .unwrap_or(0)
+}
+
+/**
+ * Compact, allocation free state of the trick phase for search.
+ * Moves are applied in place and taken back with the returned `MoveUndo`.
+ * Announcements and raises are not moves, use `set_trump` and `raise` instead.
+ * `hash` is kept up to date with every change and equals `zobrist::fingerprint` of the game.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchState {
+    pub hands: [CardMask; 4],
+    pub trump: Option<Suit>,
+    /// Called suits as bits, see `zobrist::suits_mask`
+    pub trump_called: u8,
+    pub value: i32,
+    pub phase: GamePhase,
+    pub player_at_turn: u8,
+    pub trick: [u8; 4],
+    pub trick_len: u8,
+    pub tricks_played: u8,
+    /// Trick points won by every player, without pairs
+    pub points: [i32; 4],
+    pub hash: u64,
+}
+
+/// What `SearchState::unmake_move` needs to restore the state before a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveUndo {
+    card: u8,
+    player: u8,
+    phase: GamePhase,
+    trick: [u8; 4],
+    trick_len: u8,
+    /// Winner and points if the move finished a trick
+    finished: Option<(u8, i32)>,
+    hash: u64,
+}
+
+impl SearchState {
+    /// None outside of raising and the trick phase.
+    pub fn from_game(game: &Game) -> Option<Self> {
+        let state = &game.state;
+        if !matches!(
+            state.phase,
+            GamePhase::Raising | GamePhase::StartTrick | GamePhase::Trick
+        ) {
+            return None;
+        }
+        let mut trick = [0; 4];
+        let trick_len = match state.current_trick.len() {
+            4 => 0,
+            len => len,
+        };
+        for (slot, card) in trick.iter_mut().zip(&state.current_trick[..trick_len]) {
+            *slot = card_index(card);
+        }
+        let mut points = [0; 4];
+        for finished in &state.all_tricks {
+            points[finished.winner.0 as usize] += finished.points.0;
+        }
+        Some(SearchState {
+            hands: state
+                .players
+                .clone()
+                .map(|player| cards_mask(&player.cards)),
+            trump: state.trump,
+            trump_called: suits_mask(&state.trump_called),
+            value: state.value.0,
+            phase: state.phase.clone(),
+            player_at_turn: state.player_at_turn.0,
+            trick,
+            trick_len: trick_len as u8,
+            tricks_played: state.all_tricks.len() as u8,
+            points,
+            hash: fingerprint(state),
+        })
+    }
+
+    /// Hash computed from scratch, for checking the incremental one.
+    pub fn full_hash(&self) -> u64 {
+        let mut hash = KEYS.player_at_turn[self.player_at_turn as usize]
+            ^ KEYS.phase(&self.phase)
+            ^ KEYS.value(self.value)
+            ^ KEYS.trump(self.trump)
+            ^ KEYS.called(self.trump_called);
+        for (seat, hand) in self.hands.iter().enumerate() {
+            hash ^= KEYS.hand(seat, *hand);
+        }
+        for (position, card) in self.trick[..self.trick_len as usize].iter().enumerate() {
+            hash ^= KEYS.trick[position][*card as usize];
+        }
+        hash
+    }
+
+    fn set_phase(&mut self, phase: GamePhase) {
+        self.hash ^= KEYS.phase(&self.phase) ^ KEYS.phase(&phase);
+        self.phase = phase;
+    }
+
+    /// Trump and called suits after an announcement or answer, the trick goes on.
+    pub fn set_trump(&mut self, trump: Option<Suit>, trump_called: u8) {
+        self.hash ^= KEYS.trump(self.trump) ^ KEYS.trump(trump);
+        self.hash ^= KEYS.called(self.trump_called ^ trump_called);
+        self.trump = trump;
+        self.trump_called = trump_called;
+        self.set_phase(GamePhase::Trick);
+    }
+
+    /// Raise of the playing player before the first card.
+    pub fn raise(&mut self, value: i32) {
+        self.hash ^= KEYS.value(self.value) ^ KEYS.value(value);
+        self.value = value;
+        self.set_phase(GamePhase::Trick);
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.tricks_played == 9
+    }
+
+    pub fn legal_moves(&self) -> CardMask {
+        let hand = self.hands[self.player_at_turn as usize];
+        let first_trick = hand.count_ones() == 9;
+        allowed_mask(
+            hand,
+            &self.trick[..self.trick_len as usize],
+            self.trump,
+            first_trick,
+        )
+    }
+
+    pub fn make_move(&mut self, card: u8) -> MoveUndo {
+        debug_assert!(self.legal_moves() & 1 << card != 0, "illegal move");
+        let mut undo = MoveUndo {
+            card,
+            player: self.player_at_turn,
+            phase: self.phase.clone(),
+            trick: self.trick,
+            trick_len: self.trick_len,
+            finished: None,
+            hash: self.hash,
+        };
+        let player = self.player_at_turn as usize;
+        self.hash ^= KEYS.player_at_turn[player];
+        self.hash ^= KEYS.hand[player][card as usize];
+        self.hash ^= KEYS.trick[self.trick_len as usize][card as usize];
+        self.hands[player] &= !(1 << card);
+        self.trick[self.trick_len as usize] = card;
+        self.trick_len += 1;
+        self.player_at_turn = (self.player_at_turn + 1) % 4;
+        self.set_phase(GamePhase::Trick);
+        if self.trick_len == 4 {
+            // the player at turn is the one who led the trick again
+            let winner = (self.player_at_turn + trick_winner(&self.trick, self.trump) as u8) % 4;
+            let points = trick_points(&self.trick, self.tricks_played == 8);
+            self.points[winner as usize] += points;
+            self.tricks_played += 1;
+            for (position, card) in self.trick.iter().enumerate() {
+                self.hash ^= KEYS.trick[position][*card as usize];
+            }
+            self.trick = [0; 4];
+            self.trick_len = 0;
+            self.player_at_turn = winner;
+            undo.finished = Some((winner, points));
+            self.set_phase(match self.tricks_played {
+                9 => GamePhase::Ended,
+                _ => GamePhase::StartTrick,
+            });
+        }
+        self.hash ^= KEYS.player_at_turn[self.player_at_turn as usize];
+        undo
+    }
+
+    pub fn unmake_move(&mut self, undo: MoveUndo) {
+        if let Some((winner, points)) = undo.finished {
+            self.points[winner as usize] -= points;
+            self.tricks_played -= 1;
+        }
+        self.hands[undo.player as usize] |= 1 << undo.card;
+        self.trick = undo.trick;
+        self.trick_len = undo.trick_len;
+        self.player_at_turn = undo.player;
+        self.phase = undo.phase;
+        self.hash = undo.hash;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{next_decision, Agent, RandomAgent};
+    use crate::game::cards::{allowed_cards, get_all_cards};
+    use crate::game::gameevent::ActionType;
+
+    #[test]
+    fn test_card_index() {
+        for card in get_all_cards() {
+            assert!(card_index(&card) < 36);
+            assert_eq!(card_from_index(card_index(&card)), card);
+        }
+        assert_eq!(cards_mask(&get_all_cards()), (1 << 36) - 1);
+    }
+
+    /// Plays random games with `apply_action` and the search state side by side.
+    #[test]
+    fn test_search_state_matches_game() {
+        for seed in 0..30 {
+            let names = ["A", "B", "C", "D"].map(String::from);
+            let mut game = Game::new_seeded(String::from("Search"), names, seed);
+            let mut agent = RandomAgent::new(seed);
+            let mut search: Option<SearchState> = None;
+            while let Some((_, legal)) = next_decision(&game) {
+                let card_phase = SearchState::from_game(&game).is_some();
+                if let Some(state) = search.as_ref().filter(|_| card_phase) {
+                    let cards: Vec<&Card> = game.state.player_at_turn().cards.iter().collect();
+                    let mut trick = game.state.current_trick.clone();
+                    if trick.len() == 4 {
+                        trick.clear();
+                    }
+                    let first_trick = cards.len() == 9;
+                    let expected =
+                        allowed_cards(trick.iter().collect(), cards, game.state.trump, first_trick);
+                    assert_eq!(state.legal_moves(), cards_mask(expected));
+                }
+                let action = agent.select_action(&game, &legal);
+                game = game.apply_action(action.clone()).unwrap();
+                match (&mut search, &action.action_type) {
+                    (Some(state), ActionType::CardPlayed(card)) => {
+                        let before = state.clone();
+                        let undo = state.make_move(card_index(card));
+                        let after = state.clone();
+                        state.unmake_move(undo);
+                        assert_eq!(*state, before);
+                        state.make_move(card_index(card));
+                        assert_eq!(*state, after);
+                    }
+                    (Some(state), ActionType::NewBid(value)) => state.raise(*value),
+                    (Some(state), _) => {
+                        state.set_trump(game.state.trump, suits_mask(&game.state.trump_called))
+                    }
+                    (None, _) => search = SearchState::from_game(&game),
+                }
+                // questions and answers are not part of the search state
+                if let (Some(state), Some(expected)) = (&search, SearchState::from_game(&game)) {
+                    assert_eq!(*state, expected);
+                    assert_eq!(state.hash, state.full_hash());
+                }
+            }
+            if let Some(state) = search {
+                assert!(state.is_terminal());
+                assert_eq!(state.phase, GamePhase::Ended);
+                let points: i32 = game.state.all_tricks.iter().map(|t| t.points.0).sum();
+                assert_eq!(state.points.iter().sum::<i32>(), points);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unmake_to_start() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let game = Game::new_seeded(String::from("Search"), names, 2)
+            .apply_notation("start; start; start; start; bid 120; stop; stop; stop")
+            .unwrap();
+        let pass = game.legal.iter().next().unwrap();
+        let game = game.apply_action(pass).unwrap();
+        let pass = game.legal.iter().next().unwrap();
+        let game = game.apply_action(pass).unwrap();
+        let start = SearchState::from_game(&game).unwrap();
+        let mut state = start.clone();
+        let mut undos = vec![];
+        while !state.is_terminal() {
+            let card = mask_cards(state.legal_moves()).last().unwrap();
+            undos.push(state.make_move(card));
+        }
+        assert_eq!(state.points.iter().sum::<i32>(), 140);
+        while let Some(undo) = undos.pop() {
+            state.unmake_move(undo);
+            assert_eq!(state.hash, state.full_hash());
+        }
+        assert_eq!(state, start);
+    }
+}
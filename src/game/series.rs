@@ -1,18 +1,18 @@
-#![allow(unused)]
-
 use crate::game::gameevent::GameAction;
-use crate::game::gameinfo::GameInfoPlayer;
+use crate::game::gameinfo::{GameFinishedInfo, GameInfoPlayer};
 use crate::game::player::PlaceAtTable;
 use crate::game::{current_time_string, Game};
 
-struct Series {
-    name: String,
-    created: String,
-    finished: Option<String>,
-    num_of_games: u32,
-    games: Vec<Game>,
-    players_names: [String; 4],
-    settings: SeriesSettings,
+/// Several games with the same players, the last game is the active one.
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub name: String,
+    pub created: String,
+    pub finished: Option<String>,
+    pub num_of_games: u32,
+    pub games: Vec<Game>,
+    pub players_names: [String; 4],
+    pub settings: SeriesSettings,
 }
 
 impl Series {
@@ -52,16 +52,64 @@ impl Series {
             game.apply_action_mut(action);
         }
     }
+
+    /// Whether all games of the series were played to the end.
+    pub fn is_complete(&self) -> bool {
+        self.games.len() >= self.num_of_games as usize && self.games.iter().all(Game::ended)
+    }
+
+    /**
+     * Points of the parties of seat 0 and seat 1 over the ended games.
+     * A won game scores its value for the playing party, a lost one for the opponents
+     * or, with `diff_plus_minus`, against the playing party. Schwarz games count
+     * `schwarzfactor_fifths / 5` times. A party reaching `bonus_at` gets `bonus_value` once.
+     */
+    pub fn party_points(&self) -> [i32; 2] {
+        let settings = &self.settings;
+        let mut points = [0, 0];
+        let mut bonus = [false, false];
+        for game in self.games.iter().filter(|game| game.ended()) {
+            let finished = GameFinishedInfo::from(game.clone());
+            let (Some(playing), Some(won)) = (finished.playing_party, finished.won) else {
+                continue;
+            };
+            let mut value = finished.game_value.0;
+            if finished.schwarz_game {
+                value = value * settings.schwarzfactor_fifths / 5;
+            }
+            let party = (playing.0 % 2) as usize;
+            match (won, settings.diff_plus_minus) {
+                (true, _) => points[party] += value,
+                (false, true) => points[party] -= value,
+                (false, false) => points[1 - party] += value,
+            }
+            for party in 0..2 {
+                if !bonus[party] && points[party] >= settings.bonus_at {
+                    bonus[party] = true;
+                    points[party] += settings.bonus_value;
+                }
+            }
+        }
+        points
+    }
+
+    /// Result of every seat: the difference to the other party divided by `diff_divisor`.
+    pub fn player_results(&self) -> [i32; 4] {
+        let points = self.party_points();
+        let diff = (points[0] - points[1]) / self.settings.diff_divisor.max(1);
+        [diff, -diff, diff, -diff]
+    }
 }
 
-struct SeriesSettings {
-    games: i32,
-    schwarzfactor_fifths: i32,
-    shuffle_players: bool,
-    bonus_at: i32,
-    bonus_value: i32,
-    diff_plus_minus: bool,
-    diff_divisor: i32,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeriesSettings {
+    pub games: i32,
+    pub schwarzfactor_fifths: i32,
+    pub shuffle_players: bool,
+    pub bonus_at: i32,
+    pub bonus_value: i32,
+    pub diff_plus_minus: bool,
+    pub diff_divisor: i32,
 }
 
 impl Default for SeriesSettings {
@@ -77,3 +125,31 @@ impl Default for SeriesSettings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{heuristic_agents, play_to_end};
+
+    #[test]
+    fn test_series_results() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let mut series = Series::new(String::from("Series"), names.clone(), 3, None);
+        series.games.clear();
+        for seed in 0..3 {
+            let mut agents = heuristic_agents(seed * 4);
+            let game = Game::new_seeded(String::from("Series"), names.clone(), seed);
+            series.games.push(play_to_end(game, &mut agents));
+        }
+        assert!(series.is_complete());
+
+        // seat 1 won 140, seat 0 won 130 and lost 150
+        assert_eq!(series.party_points(), [-20, 140]);
+        assert_eq!(series.player_results(), [-32, 32, -32, 32]);
+
+        series.settings.diff_plus_minus = false;
+        assert_eq!(series.party_points(), [130, 290]);
+        series.settings.bonus_at = 200;
+        assert_eq!(series.party_points(), [130, 590]);
+    }
+}
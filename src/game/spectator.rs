@@ -0,0 +1,206 @@
+use crate::game::cards::Card;
+use crate::game::errors::GameError;
+use crate::game::gameevent::{ActionType, GameEventPlayer};
+use crate::game::gameinfo::redact_event;
+use crate::game::gamestate::{FinishedTrick, GamePhase};
+use crate::game::player::PlaceAtTable;
+use crate::game::Game;
+
+/// Which hands a spectator sees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Visibility {
+    /// Only what is public at the table
+    Public,
+    /// Looks over the shoulder of one seat, sees its hand and its passes
+    Kibitz(PlaceAtTable),
+    /// Everything, for coaches and replays
+    Open,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpectatorPolicy {
+    pub visibility: Visibility,
+    /// Number of the latest events which are not shown yet
+    pub delay: usize,
+}
+
+impl SpectatorPolicy {
+    pub fn public() -> Self {
+        SpectatorPolicy {
+            visibility: Visibility::Public,
+            delay: 0,
+        }
+    }
+
+    pub fn kibitz(seat: PlaceAtTable) -> Self {
+        SpectatorPolicy {
+            visibility: Visibility::Kibitz(seat),
+            delay: 0,
+        }
+    }
+
+    pub fn open() -> Self {
+        SpectatorPolicy {
+            visibility: Visibility::Open,
+            delay: 0,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: usize) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn sees_hand(&self, seat: &PlaceAtTable) -> bool {
+        match &self.visibility {
+            Visibility::Public => false,
+            Visibility::Kibitz(kibitzed) => kibitzed == seat,
+            Visibility::Open => true,
+        }
+    }
+
+    /// Passes are seen by the passing seat and its partner who receives them.
+    pub fn sees_pass(&self, from: &PlaceAtTable) -> bool {
+        self.sees_hand(from) || self.sees_hand(&from.partner())
+    }
+}
+
+/// Everything a spectator sees, seats are in table order.
+#[derive(Debug, Clone)]
+pub struct GameInfoSpectator {
+    pub name: String,
+    pub visibility: Visibility,
+    pub player_names: [String; 4],
+    pub players_pressed_start: Vec<String>,
+    pub player_at_turn: String,
+    /// Hands of the visible seats once the game started
+    pub hands: [Option<Vec<Card>>; 4],
+    pub players_cards_number: [u8; 4],
+    pub game_phase: GamePhase,
+    pub bidding_history: Vec<(ActionType, PlaceAtTable)>,
+    pub current_trick: Vec<Card>,
+    pub last_trick: Option<FinishedTrick>,
+    pub last_event: Option<GameEventPlayer>,
+    /// Events which happened but are held back by the delay
+    pub delayed_events: usize,
+}
+
+impl GameInfoSpectator {
+    /// The view of the game as it was `policy.delay` events ago.
+    pub fn from_game(game: &Game, policy: &SpectatorPolicy) -> Result<Self, GameError> {
+        let delayed_events = policy.delay.min(game.all_events.len());
+        let shown = match delayed_events {
+            0 => game.clone(),
+            delay => replay(game, game.all_events.len() - delay)?,
+        };
+        let state = &shown.state;
+        let seats = [0, 1, 2, 3].map(PlaceAtTable);
+        Ok(GameInfoSpectator {
+            name: shown.info.name.clone(),
+            visibility: policy.visibility.clone(),
+            player_names: shown.info.player_names.clone(),
+            players_pressed_start: state.players_started(),
+            player_at_turn: state
+                .player_at_place(state.player_at_turn.clone())
+                .name
+                .clone(),
+            hands: seats.clone().map(|seat| {
+                (state.started && policy.sees_hand(&seat))
+                    .then(|| state.player_at_place(seat).cards.clone())
+            }),
+            players_cards_number: seats.map(|seat| state.player_at_place(seat).cards.len() as u8),
+            game_phase: state.phase.clone(),
+            bidding_history: state.bidding_history.clone(),
+            current_trick: state.current_trick.clone(),
+            last_trick: state.all_tricks.last().cloned(),
+            last_event: shown
+                .all_events
+                .last()
+                .map(|event| redact_event(event, policy.sees_pass(&event.last_action.player))),
+            delayed_events,
+        })
+    }
+}
+
+/// The game after its first `events` events, with the original meta info and times.
+fn replay(game: &Game, events: usize) -> Result<Game, GameError> {
+    let mut replayed = Game::new(
+        game.info.name.clone(),
+        game.info.player_names.clone(),
+        Some(game.info.player_start_cards.clone()),
+    );
+    for event in &game.all_events[..events] {
+        replayed = replayed.apply_action(event.last_action.clone())?;
+    }
+    replayed.all_events = game.all_events[..events].to_vec();
+    replayed.info = game.info.clone();
+    if !replayed.ended() {
+        replayed.info.end_time = None;
+    }
+    Ok(replayed)
+}
+
+impl Game {
+    pub fn spectator_view(&self, policy: &SpectatorPolicy) -> Result<GameInfoSpectator, GameError> {
+        GameInfoSpectator::from_game(self, policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spectator_views() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let game = Game::new_seeded(String::from("Spectate"), names, 2)
+            .apply_notation("start; start; start; start; bid 120; stop; stop; stop")
+            .unwrap();
+        let pass = game.legal.iter().next().unwrap();
+        assert_eq!(pass.player, PlaceAtTable(2));
+        let game = game.apply_action(pass).unwrap();
+
+        let public = game.spectator_view(&SpectatorPolicy::public()).unwrap();
+        assert_eq!(public.hands, [None, None, None, None]);
+        assert_eq!(public.players_cards_number, [13, 9, 5, 9]);
+        assert!(matches!(
+            public.last_event,
+            Some(GameEventPlayer::HiddenEvent)
+        ));
+
+        // seat 0 receives the cards, seat 1 does not see them
+        let kibitz = game
+            .spectator_view(&SpectatorPolicy::kibitz(PlaceAtTable(0)))
+            .unwrap();
+        assert_eq!(kibitz.hands[0].as_ref().unwrap().len(), 13);
+        assert!(kibitz.hands[1..].iter().all(Option::is_none));
+        assert!(matches!(
+            kibitz.last_event,
+            Some(GameEventPlayer::PublicEvent(_))
+        ));
+        let other = game
+            .spectator_view(&SpectatorPolicy::kibitz(PlaceAtTable(1)))
+            .unwrap();
+        assert!(matches!(
+            other.last_event,
+            Some(GameEventPlayer::HiddenEvent)
+        ));
+
+        let open = game.spectator_view(&SpectatorPolicy::open()).unwrap();
+        assert!(open.hands.iter().all(Option::is_some));
+
+        // two events ago seat 3 was still to stop
+        let delayed = game
+            .spectator_view(&SpectatorPolicy::open().with_delay(2))
+            .unwrap();
+        assert_eq!(delayed.delayed_events, 2);
+        assert_eq!(delayed.game_phase, GamePhase::Bidding);
+        assert_eq!(delayed.player_at_turn, "D");
+        assert_eq!(delayed.players_cards_number, [9; 4]);
+        let before_start = game
+            .spectator_view(&SpectatorPolicy::open().with_delay(100))
+            .unwrap();
+        assert_eq!(before_start.delayed_events, game.all_events.len());
+        assert_eq!(before_start.hands, [None, None, None, None]);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::game::cards::{Card, Suit};
+use crate::game::gamestate::{GamePhase, GameState};
+use crate::game::search::{card_index, cards_mask, CardMask};
+
+const SEED: u64 = 0x6d61_726a_6170_7573;
+const PHASES: usize = 14;
+const VALUES: usize = 62;
+
+/**
+ * Random keys for every part of the game relevant state:
+ * hands, the current trick, trump, called suits, player at turn, phase and value.
+ * A state's hash is the xor of the keys of all its parts, so moves can update it in place.
+ */
+pub struct ZobristKeys {
+    pub hand: [[u64; 36]; 4],
+    /// Card at a position of the current trick
+    pub trick: [[u64; 36]; 4],
+    pub trump: [u64; 4],
+    pub called: [u64; 4],
+    pub player_at_turn: [u64; 4],
+    phase: [u64; PHASES],
+    value: [u64; VALUES],
+}
+
+const fn split_mix(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn fill<const N: usize>(mut state: u64) -> ([u64; N], u64) {
+    let mut keys = [0; N];
+    let mut i = 0;
+    while i < N {
+        let (next, key) = split_mix(state);
+        state = next;
+        keys[i] = key;
+        i += 1;
+    }
+    (keys, state)
+}
+
+impl ZobristKeys {
+    const fn generate(state: u64) -> Self {
+        let (h0, state) = fill::<36>(state);
+        let (h1, state) = fill::<36>(state);
+        let (h2, state) = fill::<36>(state);
+        let (h3, state) = fill::<36>(state);
+        let (t0, state) = fill::<36>(state);
+        let (t1, state) = fill::<36>(state);
+        let (t2, state) = fill::<36>(state);
+        let (t3, state) = fill::<36>(state);
+        let (trump, state) = fill::<4>(state);
+        let (called, state) = fill::<4>(state);
+        let (player_at_turn, state) = fill::<4>(state);
+        let (phase, state) = fill::<PHASES>(state);
+        let (value, _) = fill::<VALUES>(state);
+        ZobristKeys {
+            hand: [h0, h1, h2, h3],
+            trick: [t0, t1, t2, t3],
+            trump,
+            called,
+            player_at_turn,
+            phase,
+            value,
+        }
+    }
+
+    pub fn phase(&self, phase: &GamePhase) -> u64 {
+        let index = match phase {
+            GamePhase::WaitingForStart => 0,
+            GamePhase::Bidding => 1,
+            GamePhase::PassingForth => 2,
+            GamePhase::PassingBack => 3,
+            GamePhase::Raising => 4,
+            GamePhase::Trick => 5,
+            GamePhase::StartTrick => 6,
+            GamePhase::AnsweringPair => 7,
+            GamePhase::AnsweringHalf(suit) => 8 + *suit as usize,
+            GamePhase::Ended => 12,
+            GamePhase::PendingUndo(previous) => return self.phase[13] ^ self.phase(previous),
+        };
+        self.phase[index]
+    }
+
+    /// Values from 115 to 420.
+    pub fn value(&self, value: i32) -> u64 {
+        self.value[((value - 115) / 5).clamp(0, VALUES as i32 - 1) as usize]
+    }
+
+    pub fn hand(&self, seat: usize, cards: CardMask) -> u64 {
+        (0..36)
+            .filter(|index| cards & 1 << index != 0)
+            .fold(0, |hash, index| hash ^ self.hand[seat][index])
+    }
+
+    pub fn called(&self, suits: u8) -> u64 {
+        (0..4)
+            .filter(|suit| suits & 1 << suit != 0)
+            .fold(0, |hash, suit| hash ^ self.called[suit])
+    }
+
+    pub fn trump(&self, trump: Option<Suit>) -> u64 {
+        trump.map_or(0, |suit| self.trump[suit as usize])
+    }
+}
+
+pub static KEYS: ZobristKeys = ZobristKeys::generate(SEED);
+
+/// Called suits as bits, green is the lowest.
+pub fn suits_mask(suits: &[Suit]) -> u8 {
+    suits.iter().fold(0, |mask, suit| mask | 1 << *suit as u8)
+}
+
+/**
+ * Hash of the game relevant state computed from scratch,
+ * equal states hash equal regardless of card order, names or history.
+ * A finished trick which is still shown counts as an empty trick.
+ */
+pub fn fingerprint(state: &GameState) -> u64 {
+    let mut hash = KEYS.player_at_turn[state.player_at_turn.0 as usize]
+        ^ KEYS.phase(&state.phase)
+        ^ KEYS.value(state.value.0)
+        ^ KEYS.trump(state.trump)
+        ^ KEYS.called(suits_mask(&state.trump_called));
+    for (seat, player) in state.players.iter().enumerate() {
+        for card in &player.cards {
+            hash ^= KEYS.hand[seat][card_index(card) as usize];
+        }
+    }
+    hash ^ trick_hash(&state.current_trick)
+}
+
+fn trick_hash(trick: &[Card]) -> u64 {
+    if trick.len() == 4 {
+        return 0;
+    }
+    trick.iter().enumerate().fold(0, |hash, (position, card)| {
+        hash ^ KEYS.trick[position][card_index(card) as usize]
+    })
+}
+
+/// Updates the hash of `next` from the one of `previous` with the keys of the parts which changed.
+pub(crate) fn update_fingerprint(previous: &GameState, next: &mut GameState) {
+    let mut hash = previous.hash;
+    if previous.player_at_turn != next.player_at_turn {
+        hash ^= KEYS.player_at_turn[previous.player_at_turn.0 as usize]
+            ^ KEYS.player_at_turn[next.player_at_turn.0 as usize];
+    }
+    if previous.phase != next.phase {
+        hash ^= KEYS.phase(&previous.phase) ^ KEYS.phase(&next.phase);
+    }
+    if previous.value != next.value {
+        hash ^= KEYS.value(previous.value.0) ^ KEYS.value(next.value.0);
+    }
+    if previous.trump != next.trump {
+        hash ^= KEYS.trump(previous.trump) ^ KEYS.trump(next.trump);
+    }
+    if previous.trump_called != next.trump_called {
+        hash ^= KEYS.called(suits_mask(&previous.trump_called))
+            ^ KEYS.called(suits_mask(&next.trump_called));
+    }
+    for seat in 0..4 {
+        let (before, after) = (&previous.players[seat].cards, &next.players[seat].cards);
+        if before != after {
+            hash ^= KEYS.hand(seat, cards_mask(before) ^ cards_mask(after));
+        }
+    }
+    if previous.current_trick != next.current_trick {
+        hash ^= trick_hash(&previous.current_trick) ^ trick_hash(&next.current_trick);
+    }
+    next.hash = hash;
+}
+
+impl GameState {
+    /// The hash kept by `Game::apply_action`, equal to `zobrist::fingerprint` of the state.
+    pub fn fingerprint(&self) -> u64 {
+        self.hash
+    }
+
+    /// Recomputes the hash after the state was changed by hand.
+    pub fn refresh_fingerprint(&mut self) {
+        self.hash = fingerprint(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use rand::prelude::IndexedRandom;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_fingerprint() {
+        let names = ["A", "B", "C", "D"].map(String::from);
+        let game = Game::new_seeded(String::from("Zobrist"), names.clone(), 4)
+            .apply_notation("start; start; start; start; bid 120")
+            .unwrap();
+        let renamed = Game::new_seeded(
+            String::from("Other"),
+            ["E", "F", "G", "H"].map(String::from),
+            4,
+        )
+        .apply_notation("start; start; start; start; bid 120")
+        .unwrap();
+        assert_eq!(game.state.fingerprint(), renamed.state.fingerprint());
+        assert_ne!(game.state, renamed.state);
+
+        // a different card order is the same state for the fingerprint only
+        let mut reordered = game.state.clone();
+        reordered.players[0].cards.reverse();
+        assert_eq!(game.state.fingerprint(), fingerprint(&reordered));
+        assert_ne!(game.state, reordered);
+
+        // same names, different hands
+        let other_deal = Game::new_seeded(String::from("Zobrist"), names, 5)
+            .apply_notation("start; start; start; start; bid 120")
+            .unwrap();
+        assert_ne!(game.state, other_deal.state);
+        assert_ne!(game.state.fingerprint(), other_deal.state.fingerprint());
+
+        let higher = game.apply_notation("bid 125").unwrap();
+        assert_ne!(game.state.fingerprint(), higher.state.fingerprint());
+        let undone = higher
+            .apply_notation("undo; undo accept; undo accept")
+            .unwrap();
+        assert_eq!(game.state.fingerprint(), undone.state.fingerprint());
+    }
+
+    #[test]
+    fn test_fingerprint_is_updated() {
+        for seed in 0..10 {
+            let names = ["A", "B", "C", "D"].map(String::from);
+            let mut game = Game::new_seeded(String::from("Zobrist"), names, seed);
+            let mut rng = StdRng::seed_from_u64(seed);
+            while !game.ended() {
+                assert_eq!(game.state.fingerprint(), fingerprint(&game.state));
+                let action = game.legal_actions().choose(&mut rng).unwrap().clone();
+                game = game.apply_action(action).unwrap();
+            }
+            assert_eq!(game.state.fingerprint(), fingerprint(&game.state));
+        }
+    }
+}
@@ -1 +1,12 @@
+pub mod ai;
+pub mod archive;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod game;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod stats;
+pub mod table;
+pub mod tournament;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,313 @@
+use std::sync::Arc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::ai::agent::{next_decision, Agent, HeuristicAgent, RandomAgent};
+use crate::ai::batch::{collect_batch, run_batch, AgentFactory, BatchConfig, BatchResult};
+use crate::ai::encoding;
+use crate::ai::export::SampleSet;
+use crate::game::gameevent::GameAction;
+use crate::game::gameinfo::{GameFinishedInfo, GameInfoPlayer};
+use crate::game::player::PlaceAtTable;
+use crate::game::Game;
+
+/*
+Python bindings, built with maturin as the `marjapussi` module.
+Actions are passed in notation with a seat, e.g. `2 bid 140`, structured data as dicts.
+ */
+
+fn value_error(e: impl std::fmt::Debug) -> PyErr {
+    PyValueError::new_err(format!("{:?}", e))
+}
+
+fn io_error(e: std::io::Error) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+fn seat(seat: u8) -> PyResult<PlaceAtTable> {
+    match seat {
+        0..=3 => Ok(PlaceAtTable(seat)),
+        _ => Err(PyValueError::new_err(format!("{} is not a seat", seat))),
+    }
+}
+
+fn to_python<'py>(py: Python<'py>, json: &serde_json::Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?
+        .call_method1("loads", (json.to_string(),))
+}
+
+fn notation(actions: &[GameAction]) -> Vec<String> {
+    actions.iter().map(|action| action.to_string()).collect()
+}
+
+#[pyclass(name = "Game", module = "marjapussi")]
+#[derive(Clone)]
+struct PyGame {
+    game: Game,
+}
+
+#[pymethods]
+impl PyGame {
+    /// A random deal, or a reproducible one with a seed.
+    #[new]
+    #[pyo3(signature = (players=None, seed=None, name=String::from("Python")))]
+    fn new(players: Option<[String; 4]>, seed: Option<u64>, name: String) -> Self {
+        let players = players.unwrap_or(["Seat 0", "Seat 1", "Seat 2", "Seat 3"].map(String::from));
+        let game = match seed {
+            Some(seed) => Game::new_seeded(name, players, seed),
+            None => Game::new(name, players, None),
+        };
+        PyGame { game }
+    }
+
+    fn legal_actions(&self) -> Vec<String> {
+        notation(&self.game.legal_actions())
+    }
+
+    /// The seat which has to decide next and its legal actions, `None` once the game ended.
+    fn next_decision(&self) -> Option<(u8, Vec<String>)> {
+        next_decision(&self.game).map(|(seat, legal)| (seat.0, notation(&legal)))
+    }
+
+    /// Applies one action, with or without a seat, the game itself is unchanged.
+    fn apply_action(&self, action: &str) -> PyResult<PyGame> {
+        let action = self.game.action_from_notation(action).map_err(io_error)?;
+        let game = self.game.apply_action(action).map_err(value_error)?;
+        Ok(PyGame { game })
+    }
+
+    /// Applies a `;` separated move list.
+    fn apply_notation(&self, moves: &str) -> PyResult<PyGame> {
+        let game = self.game.apply_notation(moves).map_err(io_error)?;
+        Ok(PyGame { game })
+    }
+
+    /// Applies the action with this index for the seat, see `action_index`.
+    fn apply_index(&self, seat: u8, index: usize) -> PyResult<PyGame> {
+        let action_type = encoding::action_from_index(index)
+            .ok_or_else(|| PyValueError::new_err(format!("{} is no action index", index)))?;
+        let action = GameAction {
+            action_type,
+            player: self::seat(seat)?,
+        };
+        let game = self.game.apply_action(action).map_err(value_error)?;
+        Ok(PyGame { game })
+    }
+
+    fn ended(&self) -> bool {
+        self.game.ended()
+    }
+
+    #[getter]
+    fn phase(&self) -> String {
+        format!("{:?}", self.game.state.phase)
+    }
+
+    #[getter]
+    fn player_at_turn(&self) -> u8 {
+        self.game.state.player_at_turn.0
+    }
+
+    #[getter]
+    fn value(&self) -> i32 {
+        self.game.state.value.0
+    }
+
+    /// What the seat sees, without the other hands.
+    fn view<'py>(&self, py: Python<'py>, seat: u8) -> PyResult<Bound<'py, PyAny>> {
+        let info = GameInfoPlayer::from_game(self.game.clone(), self::seat(seat)?);
+        to_python(py, &info.to_json())
+    }
+
+    /// Features of the seat's view, see `FEATURES`.
+    fn encode(&self, seat: u8) -> PyResult<Vec<f32>> {
+        Ok(encoding::encode_view(&self.game, &self::seat(seat)?))
+    }
+
+    /// Action indices of the seat's legal actions.
+    fn legal_indices(&self, seat: u8) -> PyResult<Vec<usize>> {
+        let seat = self::seat(seat)?;
+        Ok(self
+            .game
+            .legal
+            .iter()
+            .filter(|action| action.player == seat)
+            .map(|action| encoding::action_index(&action.action_type))
+            .collect())
+    }
+
+    /// The finished game as a dict in the archive format.
+    fn finished<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        if !self.game.ended() {
+            return Err(PyValueError::new_err("the game has not ended"));
+        }
+        let finished = GameFinishedInfo::from(self.game.clone());
+        to_python(py, &serde_json::to_value(finished).map_err(value_error)?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Game('{}', phase={:?}, value={})",
+            self.game.info.name, self.game.state.phase, self.game.state.value.0
+        )
+    }
+}
+
+#[pyfunction]
+fn action_index(action: &str) -> PyResult<usize> {
+    let action = action
+        .parse()
+        .or_else(|_| {
+            action
+                .parse::<GameAction>()
+                .map(|action| action.action_type)
+        })
+        .map_err(io_error)?;
+    encoding::try_action_index(&action)
+        .ok_or_else(|| PyValueError::new_err(format!("{:?} has no index", action)))
+}
+
+#[pyfunction]
+fn action_from_index(index: usize) -> Option<String> {
+    encoding::action_from_index(index).map(|action| action.to_string())
+}
+
+fn factory(agents: [String; 4], noise: f64) -> PyResult<Arc<dyn AgentFactory>> {
+    for agent in &agents {
+        if agent != "random" && agent != "heuristic" {
+            return Err(PyValueError::new_err(format!("unknown agent '{}'", agent)));
+        }
+    }
+    Ok(Arc::new(
+        move |seat: PlaceAtTable, seed: u64| -> Box<dyn Agent> {
+            match agents[seat.0 as usize].as_str() {
+                "random" => Box::new(RandomAgent::new(seed)),
+                _ => Box::new(HeuristicAgent::with_noise(seed, noise)),
+            }
+        },
+    ))
+}
+
+fn batch_config(games: usize, seed: u64, threads: Option<usize>) -> BatchConfig {
+    BatchConfig {
+        games,
+        seed,
+        threads: threads.unwrap_or(BatchConfig::default().threads),
+        ..BatchConfig::default()
+    }
+}
+
+/// Plays a batch of games with `random` or `heuristic` agents, returns the finished games.
+#[pyfunction]
+#[pyo3(signature = (games, seed=0, threads=None, agents=None, noise=0.0))]
+fn simulate<'py>(
+    py: Python<'py>,
+    games: usize,
+    seed: u64,
+    threads: Option<usize>,
+    agents: Option<[String; 4]>,
+    noise: f64,
+) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    let results = py.detach(|| simulate_batch(games, seed, threads, agents, noise))?;
+    results
+        .iter()
+        .map(|result| {
+            to_python(
+                py,
+                &serde_json::to_value(&result.game).map_err(value_error)?,
+            )
+        })
+        .collect()
+}
+
+/**
+ * Plays a batch and returns its training samples as a dict of flat lists,
+ * `features` has `FEATURES` numbers per sample.
+ */
+#[pyfunction]
+#[pyo3(signature = (games, seed=0, threads=None, agents=None, noise=0.0))]
+fn simulate_samples<'py>(
+    py: Python<'py>,
+    games: usize,
+    seed: u64,
+    threads: Option<usize>,
+    agents: Option<[String; 4]>,
+    noise: f64,
+) -> PyResult<Bound<'py, PyDict>> {
+    let samples = py.detach(|| {
+        simulate_batch(games, seed, threads, agents, noise).and_then(|results| samples(&results))
+    })?;
+    let dict = PyDict::new(py);
+    dict.set_item("features", samples.features)?;
+    dict.set_item("actions", samples.actions)?;
+    dict.set_item("outcomes", samples.outcomes)?;
+    Ok(dict)
+}
+
+/// The batch behind `simulate`, without the interpreter.
+fn simulate_batch(
+    games: usize,
+    seed: u64,
+    threads: Option<usize>,
+    agents: Option<[String; 4]>,
+    noise: f64,
+) -> PyResult<Vec<BatchResult>> {
+    let factory = factory(agents.unwrap_or(["heuristic"; 4].map(String::from)), noise)?;
+    Ok(collect_batch(run_batch(
+        batch_config(games, seed, threads),
+        factory,
+    )))
+}
+
+fn samples(results: &[BatchResult]) -> PyResult<SampleSet> {
+    let mut samples = SampleSet::new(false);
+    samples.add_batch(results).map_err(value_error)?;
+    Ok(samples)
+}
+
+#[pymodule]
+fn marjapussi(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGame>()?;
+    m.add_function(wrap_pyfunction!(action_index, m)?)?;
+    m.add_function(wrap_pyfunction!(action_from_index, m)?)?;
+    m.add_function(wrap_pyfunction!(simulate, m)?)?;
+    m.add_function(wrap_pyfunction!(simulate_samples, m)?)?;
+    m.add("FEATURES", encoding::FEATURES)?;
+    m.add("ACTIONS", encoding::ACTIONS)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_batch() {
+        let results = simulate_batch(6, 3, Some(2), None, 0.0).unwrap();
+        assert_eq!(results.len(), 6);
+        assert!(results
+            .iter()
+            .enumerate()
+            .all(|(i, result)| result.index == i
+                && (result.game.no_one_played || result.game.tricks.len() == 9)));
+        let again = simulate_batch(6, 3, Some(1), None, 0.0).unwrap();
+        assert_eq!(
+            serde_json::to_string(&results[5].game).unwrap(),
+            serde_json::to_string(&again[5].game).unwrap()
+        );
+
+        let agents = ["random", "heuristic", "random", "heuristic"].map(String::from);
+        let samples = samples(&simulate_batch(2, 0, None, Some(agents), 0.2).unwrap()).unwrap();
+        assert!(!samples.actions.is_empty());
+        assert_eq!(
+            samples.features.len(),
+            samples.actions.len() * encoding::FEATURES
+        );
+        assert_eq!(samples.outcomes.len(), samples.actions.len());
+
+        let unknown = ["random", "human", "random", "random"].map(String::from);
+        assert!(simulate_batch(1, 0, None, Some(unknown), 0.0).is_err());
+    }
+}
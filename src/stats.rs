@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Error;
+
+use crate::archive::{GameArchive, GameId};
+use crate::game::gameinfo::GameFinishedInfo;
+
+pub mod duplicate;
+
+/// Bid levels are grouped in steps of this size, starting at 120.
+pub const BID_LEVEL_STEP: i32 = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LevelStats {
+    pub played: u32,
+    pub won: u32,
+}
+
+impl LevelStats {
+    pub fn success_rate(&self) -> f64 {
+        rate(self.won, self.played)
+    }
+}
+
+/**
+ * Aggregated results of one player or one partnership.
+ * Points are the player's own points for players and the party's points for partnerships.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub games: u32,
+    /// Games where someone played, won or lost from this side of the table
+    pub wins: u32,
+    pub losses: u32,
+    /// Games where the final bid was made by this player or partnership
+    pub bidding_wins: u32,
+    pub bidding_won_games: u32,
+    pub bid_value_sum: i64,
+    pub by_bid_level: BTreeMap<i32, LevelStats>,
+    pub schwarz_wins: u32,
+    pub schwarz_losses: u32,
+    pub pairs_announced: u32,
+    pub points: i64,
+}
+
+impl Stats {
+    /// Average value of the games this side played.
+    pub fn average_bid(&self) -> f64 {
+        match self.bidding_wins {
+            0 => 0.0,
+            n => self.bid_value_sum as f64 / n as f64,
+        }
+    }
+
+    /// How many of the games this side played were won.
+    pub fn success_rate(&self) -> f64 {
+        rate(self.bidding_won_games, self.bidding_wins)
+    }
+
+    pub fn points_per_game(&self) -> f64 {
+        match self.games {
+            0 => 0.0,
+            n => self.points as f64 / n as f64,
+        }
+    }
+
+    fn add(&mut self, game: &GameResultView, party: u8, points: i64, pairs: u32, declared: bool) {
+        self.games += 1;
+        self.points += points;
+        self.pairs_announced += pairs;
+        if let (Some(playing_party), Some(won)) = (game.playing_party, game.won) {
+            if (playing_party == party) == won {
+                self.wins += 1;
+            } else {
+                self.losses += 1;
+            }
+            match game.party_tricks[party as usize] {
+                9 => self.schwarz_wins += 1,
+                0 => self.schwarz_losses += 1,
+                _ => {}
+            }
+        }
+        if declared {
+            self.bidding_wins += 1;
+            self.bid_value_sum += game.value as i64;
+            let level = self.by_bid_level.entry(bid_level(game.value)).or_default();
+            level.played += 1;
+            if game.won == Some(true) {
+                self.bidding_won_games += 1;
+                level.won += 1;
+            }
+        }
+    }
+}
+
+fn rate(part: u32, total: u32) -> f64 {
+    match total {
+        0 => 0.0,
+        n => part as f64 / n as f64,
+    }
+}
+
+/// Lower bound of the bid level a value belongs to, e.g. 120 for 135.
+pub fn bid_level(value: i32) -> i32 {
+    120 + (value - 120).max(0) / BID_LEVEL_STEP * BID_LEVEL_STEP
+}
+
+/// The parts of a finished game which statistics need.
+struct GameResultView {
+    value: i32,
+    playing_party: Option<u8>,
+    playing_player: Option<u8>,
+    won: Option<bool>,
+    party_tricks: [u32; 2],
+}
+
+impl GameResultView {
+    fn from_game(game: &GameFinishedInfo) -> Self {
+        let mut party_tricks = [0, 0];
+        for trick in &game.tricks {
+            party_tricks[(trick.winner.0 % 2) as usize] += 1;
+        }
+        GameResultView {
+            value: game.game_value.0,
+            playing_party: game.playing_party.as_ref().map(|p| p.0 % 2),
+            playing_player: game.playing_player().map(|p| p.0),
+            won: game.won,
+            party_tricks,
+        }
+    }
+}
+
+/// Partnerships are stored with both names sorted.
+pub fn partnership(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+/**
+ * Elo rating adapted to partnerships:
+ * a party is rated with the mean of both players and both players get the same update.
+ * Games where no one played are not rated.
+ */
+#[derive(Debug, Clone)]
+pub struct EloRatings {
+    pub initial: f64,
+    pub k_factor: f64,
+    pub ratings: HashMap<String, f64>,
+}
+
+impl Default for EloRatings {
+    fn default() -> Self {
+        EloRatings {
+            initial: 1500.0,
+            k_factor: 24.0,
+            ratings: HashMap::new(),
+        }
+    }
+}
+
+impl EloRatings {
+    pub fn rating(&self, name: &str) -> f64 {
+        self.ratings.get(name).copied().unwrap_or(self.initial)
+    }
+
+    /// Probability that the party with `rating` beats the one with `other`.
+    pub fn expected(rating: f64, other: f64) -> f64 {
+        1.0 / (1.0 + 10f64.powf((other - rating) / 400.0))
+    }
+
+    pub fn update(&mut self, game: &GameFinishedInfo) {
+        let (Some(playing_party), Some(won)) = (game.playing_party.as_ref(), game.won) else {
+            return;
+        };
+        let names = &game.info.player_names;
+        let party_rating =
+            |party: usize| (self.rating(&names[party]) + self.rating(&names[party + 2])) / 2.0;
+        let playing = (playing_party.0 % 2) as usize;
+        let defending = 1 - playing;
+        let expected = Self::expected(party_rating(playing), party_rating(defending));
+        let delta = self.k_factor * (if won { 1.0 } else { 0.0 } - expected);
+        for (party, change) in [(playing, delta), (defending, -delta)] {
+            for seat in [party, party + 2] {
+                let rating = self.rating(&names[seat]) + change;
+                self.ratings.insert(names[seat].clone(), rating);
+            }
+        }
+    }
+}
+
+/// Statistics and ratings over a whole archive, games have to be added in chronological order.
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    pub players: HashMap<String, Stats>,
+    pub partnerships: HashMap<(String, String), Stats>,
+    pub ratings: EloRatings,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics::default()
+    }
+
+    pub fn from_games<'a>(games: impl IntoIterator<Item = &'a GameFinishedInfo>) -> Self {
+        let mut statistics = Statistics::new();
+        for game in games {
+            statistics.add(game);
+        }
+        statistics
+    }
+
+    /// Adds all archived games sorted by date.
+    pub fn from_archive<A: GameArchive>(archive: &A) -> Result<Self, Error> {
+        let mut ids: Vec<GameId> = (0..archive.len()).collect();
+        ids.sort_by_key(|id| archive.index().entries[*id].date.clone());
+        let mut statistics = Statistics::new();
+        for id in ids {
+            if let Some(game) = archive.get(id)? {
+                statistics.add(&game);
+            }
+        }
+        Ok(statistics)
+    }
+
+    pub fn add(&mut self, game: &GameFinishedInfo) {
+        let view = GameResultView::from_game(game);
+        let names = &game.info.player_names;
+        let points = game.players_points();
+        let announced = game.players_announced();
+
+        for seat in 0..4 {
+            self.players.entry(names[seat].clone()).or_default().add(
+                &view,
+                (seat % 2) as u8,
+                points[seat].0 as i64,
+                announced[seat].len() as u32,
+                view.playing_player == Some(seat as u8),
+            );
+        }
+        for party in 0..2 {
+            self.partnerships
+                .entry(partnership(&names[party], &names[party + 2]))
+                .or_default()
+                .add(
+                    &view,
+                    party as u8,
+                    (points[party].0 + points[party + 2].0) as i64,
+                    (announced[party].len() + announced[party + 2].len()) as u32,
+                    view.playing_party == Some(party as u8),
+                );
+        }
+        self.ratings.update(game);
+    }
+
+    /// All players with their rating, best first.
+    pub fn leaderboard(&self) -> Vec<(String, f64, Stats)> {
+        let mut board: Vec<(String, f64, Stats)> = self
+            .players
+            .iter()
+            .map(|(name, stats)| (name.clone(), self.ratings.rating(name), stats.clone()))
+            .collect();
+        board.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::MemoryArchive;
+    use crate::game::gameevent::ActionType;
+    use crate::game::Game;
+
+    fn helper_finished(names: [&str; 4], moves: &str, seed: u64) -> GameFinishedInfo {
+        let mut game = Game::new_seeded(String::from("Stats"), names.map(String::from), seed)
+            .apply_notation(moves)
+            .unwrap();
+        // never raise and never undo
+        while !game.ended() {
+            let action = game
+                .legal
+                .iter()
+                .filter(|a| a.action_type != ActionType::UndoRequest)
+                .last()
+                .unwrap();
+            game = game.apply_action(action).unwrap();
+        }
+        GameFinishedInfo::from(game)
+    }
+
+    #[test]
+    fn test_bid_level() {
+        assert_eq!(bid_level(120), 120);
+        assert_eq!(bid_level(135), 120);
+        assert_eq!(bid_level(140), 140);
+        assert_eq!(bid_level(415), 400);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let start = "start; start; start; start";
+        let games = [
+            helper_finished(
+                ["A", "B", "C", "D"],
+                &format!("{start}; bid 140; stop; stop; stop"),
+                1,
+            ),
+            helper_finished(
+                ["B", "A", "C", "D"],
+                &format!("{start}; stop; bid 120; stop; stop"),
+                2,
+            ),
+            helper_finished(
+                ["A", "B", "C", "D"],
+                &format!("{start}; stop; stop; stop; stop"),
+                3,
+            ),
+        ];
+        let statistics = Statistics::from_games(&games);
+        let mut archive = MemoryArchive::new();
+        for game in games.iter().rev() {
+            archive.insert(game.clone()).unwrap();
+        }
+        assert_eq!(
+            Statistics::from_archive(&archive).unwrap().players,
+            statistics.players
+        );
+
+        let a = &statistics.players["A"];
+        assert_eq!(a.games, 3);
+        assert_eq!(a.bidding_wins, 2);
+        assert_eq!(a.average_bid(), 130.0);
+        assert_eq!(a.by_bid_level[&140].played, 1);
+        assert_eq!(a.wins + a.losses, 2);
+        assert_eq!(statistics.players["D"].bidding_wins, 0);
+
+        let ac = &statistics.partnerships[&partnership("C", "A")];
+        assert_eq!(ac.games, 2);
+        assert_eq!(ac.bidding_wins, 1);
+        assert_eq!(
+            statistics.partnerships[&partnership("A", "D")].bidding_wins,
+            1
+        );
+        let total: i64 = games
+            .iter()
+            .map(|g| g.players_points().iter().map(|p| p.0 as i64).sum::<i64>())
+            .sum();
+        let partnership_points: i64 = statistics.partnerships.values().map(|s| s.points).sum();
+        assert_eq!(total, partnership_points);
+
+        // elo is zero sum, the game where no one played is not rated
+        let ratings: f64 = statistics.leaderboard().iter().map(|(_, r, _)| r).sum();
+        assert!((ratings - 4.0 * 1500.0).abs() < 1e-9);
+        let first = &games[0];
+        let winner = if first.won.unwrap() { "A" } else { "B" };
+        assert!(statistics.ratings.rating(winner) != 1500.0);
+    }
+
+    #[test]
+    fn test_no_schwarz_without_a_game() {
+        let game = helper_finished(
+            ["A", "B", "C", "D"],
+            "start; start; start; start; stop; stop; stop; stop",
+            3,
+        );
+        assert!(game.no_one_played);
+        let statistics = Statistics::from_games([&game]);
+        for stats in statistics
+            .players
+            .values()
+            .chain(statistics.partnerships.values())
+        {
+            assert_eq!(stats.schwarz_wins, 0);
+            assert_eq!(stats.schwarz_losses, 0);
+        }
+    }
+}
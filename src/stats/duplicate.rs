@@ -0,0 +1,271 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::game::cards::Card;
+use crate::game::gameinfo::GameFinishedInfo;
+use crate::stats::partnership;
+
+/*
+Duplicate scoring: games dealt the same cards form a board, and every partnership is compared
+only with the others who held the same cards on the same side, which removes the luck of the deal.
+A deal rotated around the table is the same board: the hands are read from the opening seat in the
+smallest rotation, side 0 holds the first and third of those hands, side 1 the others.
+ */
+
+/// Score differences at which one more IMP is won, values are multiples of 5.
+pub const IMP_SCALE: [i32; 15] = [
+    10, 20, 40, 60, 80, 100, 130, 160, 200, 250, 300, 350, 400, 500, 600,
+];
+
+/// The hands of all seats, sorted and in the smallest rotation, identify a board.
+pub type Board = [Vec<Card>; 4];
+
+/// The board of a game and its rotation, the hand of seat `s` is at `(s + 4 - rotation) % 4`.
+pub fn board(game: &GameFinishedInfo) -> (Board, usize) {
+    let hands = game.info.player_start_cards.clone().map(|mut hand| {
+        hand.sort();
+        hand
+    });
+    (0..4)
+        .map(|rotation| {
+            let rotated: Board = [0, 1, 2, 3].map(|i| hands[(i + rotation) % 4].clone());
+            (rotated, rotation)
+        })
+        .min()
+        .expect("there are four rotations")
+}
+
+/// IMPs for a score difference, negative for a negative difference.
+pub fn imps(difference: f64) -> i32 {
+    let won = IMP_SCALE
+        .iter()
+        .take_while(|step| difference.abs() >= **step as f64)
+        .count() as i32;
+    if difference < 0.0 {
+        -won
+    } else {
+        won
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SideResult {
+    /// Index of the game in the scored games
+    pub game: usize,
+    pub side: u8,
+    pub players: (String, String),
+    /// The party's score, see `GameFinishedInfo::party_score`
+    pub score: f64,
+    /// Share of the other results on this side which were beaten, ties count half
+    pub matchpoints: f64,
+    /// Average IMPs against each other result on this side
+    pub imps: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BoardScores {
+    pub board: Board,
+    pub results: Vec<SideResult>,
+}
+
+/// Groups the games by board and scores every side against the others on the same board.
+pub fn score_boards(games: &[GameFinishedInfo]) -> Vec<BoardScores> {
+    let mut boards: Vec<BoardScores> = vec![];
+    for (i, game) in games.iter().enumerate() {
+        let (dealt, rotation) = board(game);
+        let position = match boards.iter().position(|scores| scores.board == dealt) {
+            Some(position) => position,
+            None => {
+                boards.push(BoardScores {
+                    board: dealt,
+                    results: vec![],
+                });
+                boards.len() - 1
+            }
+        };
+        let names = &game.info.player_names;
+        boards[position]
+            .results
+            .extend([0, 1].map(|party| SideResult {
+                game: i,
+                side: (party + rotation as u8) % 2,
+                players: partnership(&names[party as usize], &names[party as usize + 2]),
+                score: game.party_score(party),
+                matchpoints: 0.5,
+                imps: 0.0,
+            }));
+    }
+    for scores in &mut boards {
+        let results = scores.results.clone();
+        for result in &mut scores.results {
+            let others: Vec<f64> = results
+                .iter()
+                .filter(|other| other.side == result.side && other.game != result.game)
+                .map(|other| other.score)
+                .collect();
+            if others.is_empty() {
+                continue;
+            }
+            let beaten: f64 = others
+                .iter()
+                .map(|other| match result.score.total_cmp(other) {
+                    Ordering::Greater => 1.0,
+                    Ordering::Equal => 0.5,
+                    Ordering::Less => 0.0,
+                })
+                .sum();
+            result.matchpoints = beaten / others.len() as f64;
+            result.imps = others
+                .iter()
+                .map(|other| imps(result.score - other) as f64)
+                .sum::<f64>()
+                / others.len() as f64;
+        }
+    }
+    boards
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DuplicateTotal {
+    pub boards: u32,
+    /// Sum of the matchpoint shares, divide by `boards` for a percentage
+    pub matchpoints: f64,
+    pub imps: f64,
+}
+
+impl DuplicateTotal {
+    pub fn percentage(&self) -> f64 {
+        match self.boards {
+            0 => 0.0,
+            n => 100.0 * self.matchpoints / n as f64,
+        }
+    }
+}
+
+/// Totals of every partnership over all boards.
+pub fn partnership_totals(boards: &[BoardScores]) -> HashMap<(String, String), DuplicateTotal> {
+    let mut totals: HashMap<(String, String), DuplicateTotal> = HashMap::new();
+    for result in boards.iter().flat_map(|scores| &scores.results) {
+        let total = totals.entry(result.players.clone()).or_default();
+        total.boards += 1;
+        total.matchpoints += result.matchpoints;
+        total.imps += result.imps;
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{heuristic_agents_with_noise, play_to_end};
+    use crate::game::player::seeded_cards;
+    use crate::game::Game;
+
+    fn helper_played(names: [&str; 4], deal: u64, seed: u64) -> GameFinishedInfo {
+        helper_rotated(names, deal, 0, seed)
+    }
+
+    /// Plays the seeded deal with the hands moved `rotation` seats to the left.
+    fn helper_rotated(names: [&str; 4], deal: u64, rotation: usize, seed: u64) -> GameFinishedInfo {
+        let mut agents = heuristic_agents_with_noise(seed * 4, 0.5);
+        let hands = seeded_cards(deal);
+        let rotated = [0, 1, 2, 3].map(|seat| hands[(seat + rotation) % 4].clone());
+        let game = Game::new(
+            String::from("Duplicate"),
+            names.map(String::from),
+            Some(rotated),
+        );
+        GameFinishedInfo::from(play_to_end(game, &mut agents))
+    }
+
+    #[test]
+    fn test_imps() {
+        assert_eq!(imps(0.0), 0);
+        assert_eq!(imps(5.0), 0);
+        assert_eq!(imps(10.0), 1);
+        assert_eq!(imps(-145.0), -7);
+        assert_eq!(imps(1000.0), 15);
+    }
+
+    #[test]
+    fn test_duplicate_scoring() {
+        let games = [
+            helper_played(["A", "B", "C", "D"], 1, 0),
+            helper_played(["E", "F", "G", "H"], 1, 1),
+            helper_played(["B", "E", "D", "G"], 1, 2),
+            helper_played(["A", "B", "C", "D"], 2, 3),
+        ];
+        let boards = score_boards(&games);
+        assert_eq!(boards.len(), 2);
+        assert_eq!(boards[0].results.len(), 6);
+
+        let single = &boards[1].results;
+        assert!(single.iter().all(|r| r.matchpoints == 0.5 && r.imps == 0.0));
+
+        for side in [0, 1] {
+            let results: Vec<&SideResult> = boards[0]
+                .results
+                .iter()
+                .filter(|r| r.side == side)
+                .collect();
+            let matchpoints: f64 = results.iter().map(|r| r.matchpoints).sum();
+            assert!((matchpoints - 1.5).abs() < 1e-9);
+            let imps: f64 = results.iter().map(|r| r.imps).sum();
+            assert!(imps.abs() < 1e-9);
+        }
+        // the parties of a game are scored zero-sum, so their matchpoints are complementary
+        for pair in boards[0].results.chunks(2) {
+            assert!((pair[0].matchpoints + pair[1].matchpoints - 1.0).abs() < 1e-9);
+        }
+
+        let totals = partnership_totals(&boards);
+        let ac = &totals[&partnership("A", "C")];
+        assert_eq!(ac.boards, 2);
+        assert!((0.0..=100.0).contains(&ac.percentage()));
+        assert_eq!(totals[&partnership("E", "G")].boards, 2);
+        assert_eq!(totals[&partnership("B", "D")].boards, 3);
+    }
+
+    #[test]
+    fn test_rotated_deal_is_same_board() {
+        let games = [0, 1, 2, 3]
+            .map(|rotation| helper_rotated(["A", "B", "C", "D"], 5, rotation, rotation as u64));
+        let boards = score_boards(&games);
+        assert_eq!(boards.len(), 1);
+        assert_eq!(boards[0].results.len(), 8);
+        for result in &boards[0].results {
+            let game = &games[result.game];
+            let seat = |name: &String| {
+                game.info
+                    .player_names
+                    .iter()
+                    .position(|n| n == name)
+                    .unwrap()
+            };
+            let mut held: Vec<Vec<Card>> = [&result.players.0, &result.players.1]
+                .map(|name| {
+                    let mut hand = game.info.player_start_cards[seat(name)].clone();
+                    hand.sort();
+                    hand
+                })
+                .to_vec();
+            held.sort();
+            let side = result.side as usize;
+            let mut expected = vec![
+                boards[0].board[side].clone(),
+                boards[0].board[side + 2].clone(),
+            ];
+            expected.sort();
+            assert_eq!(held, expected);
+        }
+        for side in [0, 1] {
+            let matchpoints: f64 = boards[0]
+                .results
+                .iter()
+                .filter(|r| r.side == side)
+                .map(|r| r.matchpoints)
+                .sum();
+            assert!((matchpoints - 2.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,642 @@
+use std::sync::Arc;
+
+use crate::ai::agent::{next_decision, Agent};
+use crate::ai::batch::AgentFactory;
+use crate::game::errors::GameError;
+use crate::game::gameevent::{ActionType, GameAction};
+use crate::game::gameinfo::GameInfoPlayer;
+use crate::game::gamestate::GamePhase;
+use crate::game::player::PlaceAtTable;
+use crate::game::record::GameRecord;
+use crate::game::series::{Series, SeriesSettings};
+use crate::game::{current_time_string, Game};
+
+use self::chat::{
+    timeline, ChatMessage, ChatPolicy, MessageContent, Moderation, Moderator, TableEvent,
+};
+
+pub mod chat;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    SeatTaken,
+    NotSeated,
+    AlreadySeated,
+    /// Only possible between games
+    GameRunning,
+    NoGame,
+    SeriesRunning,
+    SeriesFinished,
+    /// The chat policy does not allow the message in this phase
+    ChatMuted,
+    MessageRejected(String),
+    Game(GameError),
+}
+
+impl From<GameError> for TableError {
+    fn from(error: GameError) -> Self {
+        TableError::Game(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Occupant {
+    Empty,
+    Human(String),
+    /// A bot, `replaces` is the player who left the running game
+    Bot {
+        name: String,
+        replaces: Option<String>,
+    },
+}
+
+impl Occupant {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Occupant::Empty => None,
+            Occupant::Human(name) | Occupant::Bot { name, .. } => Some(name),
+        }
+    }
+
+    pub fn is_bot(&self) -> bool {
+        matches!(self, Occupant::Bot { .. })
+    }
+}
+
+/**
+ * A table forming and playing games: players take seats, the game is created
+ * once all four seats are taken and starts when everyone pressed start.
+ * Players leaving a started game are replaced by bots, which move on their own
+ * after every action. Ended games can be followed by a rematch, also as a series.
+ */
+pub struct Table {
+    pub name: String,
+    seats: [Occupant; 4],
+    agents: [Option<Box<dyn Agent>>; 4],
+    factory: Arc<dyn AgentFactory>,
+    /// Games are dealt from `seed + number of game`, randomly without a seed
+    seed: Option<u64>,
+    game: Option<Game>,
+    series: Option<Series>,
+    /// Ended games which were not part of a series
+    pub history: Vec<Game>,
+    games_dealt: u64,
+    pub chat_policy: ChatPolicy,
+    moderators: Vec<Box<dyn Moderator>>,
+    /// Messages with the number of the game they belong to, in order of dealing
+    chat: Vec<(u64, ChatMessage)>,
+}
+
+impl Table {
+    pub fn new(name: String, factory: Arc<dyn AgentFactory>, seed: Option<u64>) -> Self {
+        Table {
+            name,
+            seats: [
+                Occupant::Empty,
+                Occupant::Empty,
+                Occupant::Empty,
+                Occupant::Empty,
+            ],
+            agents: [None, None, None, None],
+            factory,
+            seed,
+            game: None,
+            series: None,
+            history: vec![],
+            games_dealt: 0,
+            chat_policy: ChatPolicy::default(),
+            moderators: vec![],
+            chat: vec![],
+        }
+    }
+
+    pub fn seats(&self) -> &[Occupant; 4] {
+        &self.seats
+    }
+
+    pub fn seat_of(&self, name: &str) -> Option<PlaceAtTable> {
+        (0..4)
+            .find(|i| self.seats[*i as usize].name() == Some(name))
+            .map(PlaceAtTable)
+    }
+
+    /// The current game, in a series its active game.
+    pub fn game(&self) -> Option<&Game> {
+        match &self.series {
+            Some(series) => series.games.last(),
+            None => self.game.as_ref(),
+        }
+    }
+
+    fn game_mut(&mut self) -> Option<&mut Game> {
+        match &mut self.series {
+            Some(series) => series.games.last_mut(),
+            None => self.game.as_mut(),
+        }
+    }
+
+    pub fn series(&self) -> Option<&Series> {
+        self.series.as_ref()
+    }
+
+    /// Whether a game is being played, leaving players are replaced then.
+    fn started(&self) -> bool {
+        self.game()
+            .is_some_and(|game| game.state.started && !game.ended())
+    }
+
+    fn running(&self) -> bool {
+        self.game().is_some_and(|game| !game.ended())
+    }
+
+    pub fn game_info(&self, name: &str) -> Result<GameInfoPlayer, TableError> {
+        let seat = self.seat_of(name).ok_or(TableError::NotSeated)?;
+        let game = self.game().ok_or(TableError::NoGame)?;
+        Ok(GameInfoPlayer::from_game(game.clone(), seat))
+    }
+
+    /**
+     * Seats a player on the given or the first free seat.
+     * During a started game only bots can be replaced, preferably the one which took the player's place.
+     */
+    pub fn sit(
+        &mut self,
+        name: &str,
+        seat: Option<PlaceAtTable>,
+    ) -> Result<PlaceAtTable, TableError> {
+        if self.seat_of(name).is_some() {
+            return Err(TableError::AlreadySeated);
+        }
+        let started = self.started();
+        let free = |occupant: &Occupant| match occupant {
+            Occupant::Empty => !started,
+            Occupant::Bot { .. } => started,
+            Occupant::Human(_) => false,
+        };
+        let seat = match seat {
+            Some(seat) if free(&self.seats[seat.0 as usize]) => seat,
+            Some(_) => return Err(TableError::SeatTaken),
+            None => (0..4)
+                .find(|i| {
+                    matches!(&self.seats[*i as usize], Occupant::Bot { replaces: Some(r), .. } if r == name)
+                })
+                .or_else(|| (0..4).find(|i| free(&self.seats[*i as usize])))
+                .map(PlaceAtTable)
+                .ok_or(TableError::SeatTaken)?,
+        };
+        self.seats[seat.0 as usize] = Occupant::Human(name.to_string());
+        self.agents[seat.0 as usize] = None;
+        self.deal_if_full();
+        self.play_bots()?;
+        Ok(seat)
+    }
+
+    /// Fills an empty seat with a bot, it presses start on its own.
+    pub fn add_bot(&mut self, seat: PlaceAtTable) -> Result<(), TableError> {
+        if self.seats[seat.0 as usize] != Occupant::Empty {
+            return Err(TableError::SeatTaken);
+        }
+        self.seats[seat.0 as usize] = Occupant::Bot {
+            name: format!("Bot {}", seat.0),
+            replaces: None,
+        };
+        self.agents[seat.0 as usize] = Some(self.create_agent(&seat));
+        self.deal_if_full();
+        self.play_bots()
+    }
+
+    /**
+     * A player leaves the table. While a game is played a bot takes over,
+     * otherwise the seat is freed and a game waiting for its start is discarded.
+     */
+    pub fn leave(&mut self, name: &str) -> Result<(), TableError> {
+        let seat = self.seat_of(name).ok_or(TableError::NotSeated)?;
+        if self.started() {
+            self.seats[seat.0 as usize] = Occupant::Bot {
+                name: format!("{} (bot)", name),
+                replaces: Some(name.to_string()),
+            };
+            self.agents[seat.0 as usize] = Some(self.create_agent(&seat));
+            return self.play_bots();
+        }
+        self.seats[seat.0 as usize] = Occupant::Empty;
+        self.agents[seat.0 as usize] = None;
+        if self.game().is_some_and(|game| !game.state.started) {
+            match &mut self.series {
+                Some(series) => drop(series.games.pop()),
+                None => self.game = None,
+            }
+        }
+        Ok(())
+    }
+
+    /// Presses start for the player, the game begins when all four did.
+    pub fn ready(&mut self, name: &str) -> Result<(), TableError> {
+        self.act(name, ActionType::Start)
+    }
+
+    /// Applies an action of a seated player, then lets the bots move.
+    pub fn act(&mut self, name: &str, action_type: ActionType) -> Result<(), TableError> {
+        let player = self.seat_of(name).ok_or(TableError::NotSeated)?;
+        let game = self.game_mut().ok_or(TableError::NoGame)?;
+        *game = game.apply_action(GameAction {
+            action_type,
+            player,
+        })?;
+        self.play_bots()
+    }
+
+    /// Moves every occupant one seat further, only between games and outside of a series.
+    pub fn rotate_seats(&mut self) -> Result<(), TableError> {
+        if self.series.is_some() {
+            return Err(TableError::SeriesRunning);
+        }
+        if self.running() {
+            return Err(TableError::GameRunning);
+        }
+        self.seats.rotate_right(1);
+        self.agents.rotate_right(1);
+        Ok(())
+    }
+
+    /**
+     * Deals the next game with the same players after the current one ended,
+     * optionally rotating the seats first. Bots which replaced players stay.
+     * In a series this is the next game of the series, seats cannot be rotated then.
+     * Nothing changes if a seat is empty.
+     */
+    pub fn rematch(&mut self, rotate: bool) -> Result<(), TableError> {
+        if self.running() {
+            return Err(TableError::GameRunning);
+        }
+        if self.seats.contains(&Occupant::Empty) {
+            return Err(TableError::NoGame);
+        }
+        if let Some(series) = &self.series {
+            if rotate {
+                return Err(TableError::SeriesRunning);
+            }
+            if series.games.len() >= series.num_of_games as usize {
+                return Err(TableError::SeriesFinished);
+            }
+        } else if rotate {
+            self.rotate_seats()?;
+        }
+        self.history.extend(self.game.take());
+        self.deal_if_full();
+        self.game().ok_or(TableError::NoGame)?;
+        self.play_bots()
+    }
+
+    /// Plays the next games as a series, the current game has to be ended or waiting.
+    pub fn start_series(
+        &mut self,
+        num_of_games: u32,
+        settings: Option<SeriesSettings>,
+    ) -> Result<(), TableError> {
+        if self.series.is_some() {
+            return Err(TableError::SeriesRunning);
+        }
+        if self.started() {
+            return Err(TableError::GameRunning);
+        }
+        let game = match self.game.take() {
+            Some(game) if !game.state.started => game,
+            ended => {
+                self.history.extend(ended);
+                self.deal().ok_or(TableError::NoGame)?
+            }
+        };
+        let mut series = Series::new(
+            self.name.clone(),
+            game.info.player_names.clone(),
+            num_of_games,
+            settings,
+        );
+        series.games = vec![game];
+        self.series = Some(series);
+        self.play_bots()
+    }
+
+    /// Takes the series off the table once it is over or to abort it.
+    pub fn end_series(&mut self) -> Option<Series> {
+        let mut series = self.series.take()?;
+        if series.finished.is_none() {
+            series.finished = Some(current_time_string());
+        }
+        Some(series)
+    }
+
+    /// Moderators review messages in the order they were added.
+    pub fn add_moderator(&mut self, moderator: impl Moderator + 'static) {
+        self.moderators.push(Box::new(moderator));
+    }
+
+    /// Number of the game on the table, messages without a game belong to the next one.
+    fn game_number(&self) -> u64 {
+        match self.game() {
+            Some(_) => self.games_dealt - 1,
+            None => self.games_dealt,
+        }
+    }
+
+    /**
+     * A seated player says something, subject to the chat policy of the current phase
+     * and the moderators. Returns the message as it is shown.
+     */
+    pub fn say(&mut self, name: &str, content: MessageContent) -> Result<ChatMessage, TableError> {
+        self.seat_of(name).ok_or(TableError::NotSeated)?;
+        let phase = self
+            .game()
+            .map_or(GamePhase::WaitingForStart, |game| game.state.phase.clone());
+        if !self.chat_policy.restriction(&phase).allows(&content) {
+            return Err(TableError::ChatMuted);
+        }
+        let mut message = ChatMessage {
+            sender: name.to_string(),
+            content,
+            time: current_time_string(),
+            after_event: self.game().map_or(0, |game| game.all_events.len()),
+        };
+        for moderator in &mut self.moderators {
+            match moderator.moderate(&message) {
+                Moderation::Allow => {}
+                Moderation::Replace(text) => message.content = MessageContent::Text(text),
+                Moderation::Reject(reason) => return Err(TableError::MessageRejected(reason)),
+            }
+        }
+        self.chat.push((self.game_number(), message.clone()));
+        Ok(message)
+    }
+
+    /// All messages with the number of their game, games are numbered in the order they were dealt.
+    pub fn chat(&self) -> &[(u64, ChatMessage)] {
+        &self.chat
+    }
+
+    /// Messages of the current game.
+    pub fn messages(&self) -> Vec<&ChatMessage> {
+        let number = self.game_number();
+        self.chat
+            .iter()
+            .filter(|(game, _)| *game == number)
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    /// Events and messages of the current game in the order they happened.
+    pub fn timeline(&self) -> Vec<TableEvent> {
+        let chat: Vec<ChatMessage> = self.messages().into_iter().cloned().collect();
+        let events = self.game().map_or(&[][..], |game| &game.all_events[..]);
+        timeline(events, &chat)
+    }
+
+    /// Record of the current game including its chat.
+    pub fn record(&self) -> Option<GameRecord> {
+        let mut record = GameRecord::from_game(self.game()?);
+        let chat: Vec<ChatMessage> = self.messages().into_iter().cloned().collect();
+        record.add_chat(&chat);
+        Some(record)
+    }
+
+    fn create_agent(&self, seat: &PlaceAtTable) -> Box<dyn Agent> {
+        let seed = self.seed.unwrap_or(0).wrapping_add(self.games_dealt);
+        self.factory.create(
+            seat.clone(),
+            seed.wrapping_mul(4).wrapping_add(seat.0 as u64),
+        )
+    }
+
+    /// The next game for the seated players, none while a seat is empty.
+    fn deal(&mut self) -> Option<Game> {
+        let names = [0, 1, 2, 3].map(|i| self.seats[i].name().map(String::from));
+        if names.contains(&None) {
+            return None;
+        }
+        let names = names.map(Option::unwrap);
+        let game = match self.seed {
+            Some(seed) => Game::new_seeded(
+                self.name.clone(),
+                names,
+                seed.wrapping_add(self.games_dealt),
+            ),
+            None => Game::new(self.name.clone(), names, None),
+        };
+        self.games_dealt += 1;
+        Some(game)
+    }
+
+    /// Creates the waiting game once all seats are taken and no game is on the table.
+    fn deal_if_full(&mut self) {
+        if self.seats.contains(&Occupant::Empty) {
+            return;
+        }
+        let needs_game = match &self.series {
+            Some(series) => series.games.last().is_none_or(|game| game.ended()),
+            None => self.game.is_none(),
+        };
+        if !needs_game {
+            return;
+        }
+        let Some(game) = self.deal() else {
+            return;
+        };
+        match &mut self.series {
+            Some(series) => series.games.push(game),
+            None => self.game = Some(game),
+        }
+    }
+
+    /// Lets bots press start and decide until a human is to act.
+    fn play_bots(&mut self) -> Result<(), TableError> {
+        let Some(mut game) = self.game().cloned() else {
+            return Ok(());
+        };
+        loop {
+            let bot_start = game.legal.iter().find(|action| {
+                action.action_type == ActionType::Start
+                    && self.agents[action.player.0 as usize].is_some()
+            });
+            if let Some(start) = bot_start {
+                game = game.apply_action(start)?;
+                continue;
+            }
+            if game.state.phase == GamePhase::WaitingForStart {
+                break;
+            }
+            let Some((seat, legal)) = next_decision(&game) else {
+                break;
+            };
+            let Some(agent) = self.agents[seat.0 as usize].as_mut() else {
+                break;
+            };
+            let action = agent.select_action(&game, &legal);
+            game = game.apply_action(action)?;
+        }
+        if let Some(current) = self.game_mut() {
+            *current = game;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::HeuristicAgent;
+
+    fn helper_table() -> Table {
+        let factory: Arc<dyn AgentFactory> =
+            Arc::new(|_: PlaceAtTable, seed: u64| -> Box<dyn Agent> {
+                Box::new(HeuristicAgent::new(seed))
+            });
+        Table::new(String::from("Table"), factory, Some(3))
+    }
+
+    /// Lets the humans play their first legal action until the game ended.
+    fn helper_play_out(table: &mut Table) {
+        while let Some((seat, legal)) = table.game().and_then(next_decision) {
+            let name = table.seats()[seat.0 as usize].name().unwrap().to_string();
+            table.act(&name, legal[0].action_type.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_table_lifecycle() {
+        let mut table = helper_table();
+        assert_eq!(table.sit("Ann", None), Ok(PlaceAtTable(0)));
+        assert_eq!(table.sit("Ann", None), Err(TableError::AlreadySeated));
+        assert_eq!(table.sit("Ben", Some(PlaceAtTable(2))), Ok(PlaceAtTable(2)));
+        assert_eq!(
+            table.sit("Cid", Some(PlaceAtTable(2))),
+            Err(TableError::SeatTaken)
+        );
+        table.add_bot(PlaceAtTable(1)).unwrap();
+        assert!(table.game().is_none());
+        assert_eq!(table.ready("Ann"), Err(TableError::NoGame));
+
+        assert_eq!(table.sit("Cid", None), Ok(PlaceAtTable(3)));
+        let game = table.game().unwrap();
+        assert_eq!(game.state.players_started, vec![PlaceAtTable(1)]);
+        assert_eq!(game.info.player_names[1], "Bot 1");
+
+        // leaving before the start frees the seat again
+        table.leave("Cid").unwrap();
+        assert!(table.game().is_none());
+        table.sit("Dee", None).unwrap();
+        for name in ["Ann", "Ben", "Dee"] {
+            table.ready(name).unwrap();
+        }
+        assert!(table.started());
+
+        // a leaving player is replaced by a bot and can come back
+        table.leave("Ben").unwrap();
+        assert!(table.seats()[2].is_bot());
+        assert_eq!(
+            table.sit("Eve", Some(PlaceAtTable(0))),
+            Err(TableError::SeatTaken)
+        );
+        assert_eq!(table.sit("Ben", None), Ok(PlaceAtTable(2)));
+
+        assert_eq!(table.rematch(false), Err(TableError::GameRunning));
+        helper_play_out(&mut table);
+        assert!(table.game().unwrap().ended());
+
+        let first_deal = table.game().unwrap().info.player_start_cards.clone();
+        // without all players the ended game stays on the table
+        table.leave("Dee").unwrap();
+        assert_eq!(table.rematch(true), Err(TableError::NoGame));
+        assert!(table.game().unwrap().ended());
+        assert!(table.history.is_empty());
+        assert_eq!(table.seat_of("Ann"), Some(PlaceAtTable(0)));
+        table.sit("Dee", None).unwrap();
+        table.rematch(true).unwrap();
+        assert_eq!(table.history.len(), 1);
+        assert_eq!(table.seat_of("Ann"), Some(PlaceAtTable(1)));
+        assert_eq!(table.seat_of("Bot 1"), Some(PlaceAtTable(2)));
+        let game = table.game().unwrap();
+        assert_ne!(game.info.player_start_cards, first_deal);
+        assert_eq!(game.info.player_names[1], "Ann");
+    }
+
+    #[test]
+    fn test_table_series() {
+        let mut table = helper_table();
+        for name in ["Ann", "Ben", "Cid", "Dee"] {
+            table.sit(name, None).unwrap();
+        }
+        table.start_series(2, None).unwrap();
+        assert_eq!(table.rotate_seats(), Err(TableError::SeriesRunning));
+        for round in 0..2 {
+            for name in ["Ann", "Ben", "Cid", "Dee"] {
+                table.ready(name).unwrap();
+            }
+            helper_play_out(&mut table);
+            assert_eq!(table.series().unwrap().games.len(), round + 1);
+            if round == 0 {
+                assert_eq!(table.rematch(true), Err(TableError::SeriesRunning));
+                table.rematch(false).unwrap();
+            }
+        }
+        assert_eq!(table.rematch(false), Err(TableError::SeriesFinished));
+        let series = table.end_series().unwrap();
+        assert!(series.finished.is_some());
+        assert!(series.games.iter().all(Game::ended));
+        assert!(table.history.is_empty());
+    }
+
+    #[test]
+    fn test_table_chat() {
+        let mut table = helper_table();
+        table.add_moderator(|message: &ChatMessage| match &message.content {
+            MessageContent::Text(text) if text.contains("spam") => {
+                Moderation::Reject(String::from("no spam"))
+            }
+            MessageContent::Text(text) => Moderation::Replace(text.replace("darn", "****")),
+            MessageContent::Emote(_) => Moderation::Allow,
+        });
+        table.sit("Ann", None).unwrap();
+        assert_eq!(
+            table.say("Ben", MessageContent::from("hi")),
+            Err(TableError::NotSeated)
+        );
+        for name in ["Ben", "Cid", "Dee"] {
+            table.sit(name, None).unwrap();
+        }
+        let said = table.say("Ann", MessageContent::from("darn, hi")).unwrap();
+        assert_eq!(said.content, MessageContent::from("****, hi"));
+        assert_eq!(
+            table.say("Ben", MessageContent::from("spam")),
+            Err(TableError::MessageRejected(String::from("no spam")))
+        );
+        for name in ["Ann", "Ben", "Cid", "Dee"] {
+            table.ready(name).unwrap();
+        }
+        assert_eq!(
+            table.say("Ann", MessageContent::from("I hold the red pair")),
+            Err(TableError::ChatMuted)
+        );
+        table
+            .say("Ben", MessageContent::from(":good-luck:"))
+            .unwrap();
+
+        let timeline = table.timeline();
+        assert_eq!(timeline.len(), 6);
+        assert!(matches!(&timeline[0], TableEvent::Chat(m) if m.sender == "Ann"));
+        assert!(matches!(&timeline[5], TableEvent::Chat(m) if m.after_event == 4));
+
+        let record = table.record().unwrap();
+        let record: GameRecord = record.to_string().parse().unwrap();
+        let chat = record.chat().unwrap();
+        assert_eq!(chat.len(), 2);
+        assert_eq!(chat[1].content, MessageContent::from(":good-luck:"));
+
+        helper_play_out(&mut table);
+        table
+            .say("Cid", MessageContent::from("well played"))
+            .unwrap();
+        table.rematch(false).unwrap();
+        assert!(table.messages().is_empty());
+        assert_eq!(table.chat().len(), 3);
+    }
+}
@@ -0,0 +1,398 @@
+use std::fmt;
+use std::io::Error;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::game::gameevent::GameEvent;
+use crate::game::gamestate::GamePhase;
+use crate::game::record::GameRecord;
+
+/*
+Table talk next to the game. Free text can tell the partner about a hand,
+so chat is restricted by phase and only predefined emotes may be allowed while playing.
+Messages are placed in the event stream by the number of game events before them.
+ */
+
+/// Record tag holding one chat message.
+pub const CHAT_TAG: &str = "Chat";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize)]
+pub enum Emote {
+    Hello,
+    GoodLuck,
+    WellPlayed,
+    Thanks,
+    Oops,
+    Sorry,
+    GoodGame,
+}
+
+impl Emote {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Emote::Hello => "hello",
+            Emote::GoodLuck => "good-luck",
+            Emote::WellPlayed => "well-played",
+            Emote::Thanks => "thanks",
+            Emote::Oops => "oops",
+            Emote::Sorry => "sorry",
+            Emote::GoodGame => "good-game",
+        }
+    }
+}
+
+impl fmt::Display for Emote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ":{}:", self.code())
+    }
+}
+
+impl FromStr for Emote {
+    type Err = Error;
+
+    /// Parses the code with or without colons, e.g. `:good-luck:`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim().trim_matches(':');
+        Emote::iter()
+            .find(|emote| emote.code() == code)
+            .ok_or_else(|| Error::other(format!("'{}' is no emote.", s)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageContent {
+    Text(String),
+    Emote(Emote),
+}
+
+impl fmt::Display for MessageContent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MessageContent::Text(text) => write!(f, "{}", text),
+            MessageContent::Emote(emote) => write!(f, "{}", emote),
+        }
+    }
+}
+
+impl From<&str> for MessageContent {
+    /// An emote code becomes the emote, everything else text.
+    fn from(s: &str) -> Self {
+        match s.parse() {
+            Ok(emote) if s.starts_with(':') => MessageContent::Emote(emote),
+            _ => MessageContent::Text(s.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub sender: String,
+    pub content: MessageContent,
+    pub time: String,
+    /// Number of game events which happened before the message
+    pub after_event: usize,
+}
+
+/// Escapes the character which ends the field in a message.
+fn escape_field(value: &str, end: char) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(end, &format!("\\{}", end))
+}
+
+/// Removes the escaping backslashes of `escape_field` and `escape_text`.
+fn unescape_field(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Splits at the first delimiter which is not escaped.
+fn split_unescaped<'a>(value: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if value[i..].starts_with(delimiter) {
+            return Some((&value[..i], &value[i + delimiter.len()..]));
+        }
+    }
+    None
+}
+
+/// Text starting with a colon is escaped so that it is not read as an emote.
+fn escape_text(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\");
+    match escaped.starts_with(':') {
+        true => format!("\\{}", escaped),
+        false => escaped,
+    }
+}
+
+impl fmt::Display for ChatMessage {
+    /// Sender and time are escaped, so any name and text can be parsed back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let content = match &self.content {
+            MessageContent::Text(text) => escape_text(text),
+            MessageContent::Emote(emote) => emote.to_string(),
+        };
+        write!(
+            f,
+            "{} [{}] {}: {}",
+            self.after_event,
+            escape_field(&self.time, ']'),
+            escape_field(&self.sender, ':'),
+            content
+        )
+    }
+}
+
+impl FromStr for ChatMessage {
+    type Err = Error;
+
+    /// Parses the format written by `Display`, e.g. `4 [2023-04-15 22:45:13] Ann: :good-luck:`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::other(format!("The message '{}' could not be parsed.", s));
+        let (after_event, rest) = s.split_once(" [").ok_or_else(err)?;
+        let (time, rest) = split_unescaped(rest, "] ").ok_or_else(err)?;
+        let (sender, content) = split_unescaped(rest, ": ").ok_or_else(err)?;
+        let content = match Emote::iter().find(|emote| emote.to_string() == content) {
+            Some(emote) => MessageContent::Emote(emote),
+            None => MessageContent::Text(unescape_field(content)),
+        };
+        Ok(ChatMessage {
+            sender: unescape_field(sender),
+            content,
+            time: unescape_field(time),
+            after_event: after_event.parse().map_err(|_| err())?,
+        })
+    }
+}
+
+/// Game events and chat in the order they happened.
+#[derive(Debug, Clone)]
+pub enum TableEvent {
+    Game(GameEvent),
+    Chat(ChatMessage),
+}
+
+/// Merges the chat into the events, messages follow the events they were sent after.
+pub fn timeline(events: &[GameEvent], chat: &[ChatMessage]) -> Vec<TableEvent> {
+    let mut timeline = vec![];
+    let mut messages = chat.iter().peekable();
+    for i in 0..=events.len() {
+        while let Some(message) = messages.next_if(|m| m.after_event <= i) {
+            timeline.push(TableEvent::Chat(message.clone()));
+        }
+        if let Some(event) = events.get(i) {
+            timeline.push(TableEvent::Game(event.clone()));
+        }
+    }
+    timeline.extend(messages.cloned().map(TableEvent::Chat));
+    timeline
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRestriction {
+    Open,
+    EmotesOnly,
+    Muted,
+}
+
+impl ChatRestriction {
+    pub fn allows(&self, content: &MessageContent) -> bool {
+        match self {
+            ChatRestriction::Open => true,
+            ChatRestriction::EmotesOnly => matches!(content, MessageContent::Emote(_)),
+            ChatRestriction::Muted => false,
+        }
+    }
+}
+
+/// What may be said in which part of the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatPolicy {
+    /// Before the start and after the end
+    pub between_games: ChatRestriction,
+    pub bidding: ChatRestriction,
+    /// Passing cards and raising afterwards
+    pub passing: ChatRestriction,
+    /// Tricks, questions and answers
+    pub tricks: ChatRestriction,
+}
+
+impl ChatPolicy {
+    /// Free chat only between games, emotes during play.
+    pub fn table_talk_safe() -> Self {
+        ChatPolicy {
+            between_games: ChatRestriction::Open,
+            bidding: ChatRestriction::EmotesOnly,
+            passing: ChatRestriction::EmotesOnly,
+            tricks: ChatRestriction::EmotesOnly,
+        }
+    }
+
+    /// Free chat all the time, for friendly games.
+    pub fn open() -> Self {
+        ChatPolicy {
+            between_games: ChatRestriction::Open,
+            bidding: ChatRestriction::Open,
+            passing: ChatRestriction::Open,
+            tricks: ChatRestriction::Open,
+        }
+    }
+
+    pub fn with_tricks(mut self, restriction: ChatRestriction) -> Self {
+        self.tricks = restriction;
+        self
+    }
+
+    pub fn restriction(&self, phase: &GamePhase) -> ChatRestriction {
+        match phase {
+            GamePhase::WaitingForStart | GamePhase::Ended => self.between_games,
+            GamePhase::Bidding => self.bidding,
+            GamePhase::PassingForth | GamePhase::PassingBack | GamePhase::Raising => self.passing,
+            GamePhase::Trick
+            | GamePhase::StartTrick
+            | GamePhase::AnsweringPair
+            | GamePhase::AnsweringHalf(_) => self.tricks,
+            GamePhase::PendingUndo(phase) => self.restriction(phase),
+        }
+    }
+}
+
+impl Default for ChatPolicy {
+    fn default() -> Self {
+        ChatPolicy::table_talk_safe()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Moderation {
+    Allow,
+    /// Shows the text instead of the message
+    Replace(String),
+    /// Drops the message, the reason is told to the sender
+    Reject(String),
+}
+
+/// Hook reviewing every message which passed the chat policy, e.g. word filters or rate limits.
+pub trait Moderator: Send {
+    fn moderate(&mut self, message: &ChatMessage) -> Moderation;
+}
+
+impl<F> Moderator for F
+where
+    F: FnMut(&ChatMessage) -> Moderation + Send,
+{
+    fn moderate(&mut self, message: &ChatMessage) -> Moderation {
+        self(message)
+    }
+}
+
+impl GameRecord {
+    /// Adds the messages as `Chat` tags.
+    pub fn add_chat(&mut self, chat: &[ChatMessage]) {
+        self.extra_tags.extend(
+            chat.iter()
+                .map(|message| (CHAT_TAG.to_string(), message.to_string())),
+        );
+    }
+
+    /// The messages stored in `Chat` tags.
+    pub fn chat(&self) -> Result<Vec<ChatMessage>, Error> {
+        self.extra_tags
+            .iter()
+            .filter(|(key, _)| key == CHAT_TAG)
+            .map(|(_, value)| value.parse())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_message_round_trip() {
+        for emote in Emote::iter() {
+            assert_eq!(emote.to_string().parse::<Emote>().unwrap(), emote);
+        }
+        let messages = [
+            ChatMessage {
+                sender: String::from("Ann"),
+                content: MessageContent::Emote(Emote::GoodLuck),
+                time: String::from("2023-04-15 22:45:13"),
+                after_event: 4,
+            },
+            ChatMessage {
+                sender: String::from("Ben"),
+                content: MessageContent::Text(String::from("nice: well done [really]")),
+                time: String::new(),
+                after_event: 0,
+            },
+        ];
+        for message in messages {
+            assert_eq!(message.to_string().parse::<ChatMessage>().unwrap(), message);
+        }
+        assert_eq!(
+            MessageContent::from("oops"),
+            MessageContent::Text(String::from("oops"))
+        );
+    }
+
+    #[test]
+    fn test_chat_record_round_trip() {
+        let names = ["Ann: ] x", "Ben", "Cid", "Dan"].map(String::from);
+        let game = crate::game::Game::new_seeded(String::from("Chat"), names, 2);
+        let message = |sender: &str, content| ChatMessage {
+            sender: sender.to_string(),
+            content,
+            time: String::from("2023-04-15 22:45:13"),
+            after_event: 0,
+        };
+        let chat = vec![
+            message("Ann: ] x", MessageContent::Emote(Emote::Oops)),
+            message("Ben", MessageContent::Text(String::from(":oops:"))),
+            message(
+                "Cid",
+                MessageContent::Text(String::from("  first line\nsecond \"line\" \\ ")),
+            ),
+            message(
+                " Dan ",
+                MessageContent::Text(String::from("\\:sorry: [ok]: ")),
+            ),
+        ];
+        let mut record = GameRecord::from_game(&game);
+        record.add_chat(&chat);
+        let parsed: GameRecord = record.to_string().parse().unwrap();
+        assert_eq!(parsed.chat().unwrap(), chat);
+        assert_eq!(parsed, record);
+    }
+
+    #[test]
+    fn test_chat_policy() {
+        let policy = ChatPolicy::default();
+        let text = MessageContent::from("I have the red pair");
+        let emote = MessageContent::from(":oops:");
+        assert!(policy.restriction(&GamePhase::Ended).allows(&text));
+        assert!(!policy.restriction(&GamePhase::Bidding).allows(&text));
+        assert!(policy.restriction(&GamePhase::Bidding).allows(&emote));
+
+        let muted = ChatPolicy::open().with_tricks(ChatRestriction::Muted);
+        assert!(muted.restriction(&GamePhase::Raising).allows(&text));
+        let undo = GamePhase::PendingUndo(Box::new(GamePhase::StartTrick));
+        assert!(!muted.restriction(&undo).allows(&emote));
+    }
+}
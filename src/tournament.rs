@@ -0,0 +1,442 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::game::player::seeded_cards;
+use crate::game::series::{Series, SeriesSettings};
+use crate::game::Game;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TournamentError {
+    /// A tournament needs a multiple of four players
+    PlayerCount,
+    DuplicatePlayer,
+    NoRound,
+    RoundNotFinished,
+    TournamentFinished,
+    UnknownTable,
+    /// The series was not played by the players assigned to the table
+    WrongPlayers,
+    SeriesNotComplete,
+    /// A game of the series was not dealt the cards of the round
+    WrongDeal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pairing {
+    /// Everyone partners everyone else once in `players - 1` rounds
+    RoundRobin,
+    /// Tables are formed from neighbours in the standings
+    Swiss,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TournamentSettings {
+    pub pairing: Pairing,
+    pub rounds: usize,
+    /// Length of the series every table plays per round
+    pub games_per_round: u32,
+    /// Deals and the first Swiss round are derived from it
+    pub seed: u64,
+    pub series: SeriesSettings,
+}
+
+impl Default for TournamentSettings {
+    fn default() -> Self {
+        TournamentSettings {
+            pairing: Pairing::Swiss,
+            rounds: 3,
+            games_per_round: 4,
+            seed: 0,
+            series: SeriesSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TableAssignment {
+    /// Seat order, partners sit opposite each other
+    pub players: [String; 4],
+    /// The reported series once it is complete
+    pub series: Option<Series>,
+}
+
+/// One round, every table plays the same deals in the same seats.
+#[derive(Debug, Clone)]
+pub struct Round {
+    pub name: String,
+    /// Game `i` of every table is dealt from seed `first_deal + i`
+    pub first_deal: u64,
+    pub games: u32,
+    pub tables: Vec<TableAssignment>,
+}
+
+impl Round {
+    pub fn deal_seed(&self, game: u32) -> u64 {
+        self.first_deal.wrapping_add(game as u64)
+    }
+
+    /// Game `game` of the series at the table, none after the last one.
+    pub fn game(&self, table: usize, game: u32) -> Option<Game> {
+        let assignment = self.tables.get(table)?;
+        (game < self.games).then(|| {
+            Game::new_seeded(
+                format!("{} table {}", self.name, table + 1),
+                assignment.players.clone(),
+                self.deal_seed(game),
+            )
+        })
+    }
+
+    /**
+     * The series to play at the table with its first game dealt, later games come from `game`.
+     * A `table::Table` seeded with `first_deal` deals the same games in a series.
+     */
+    pub fn series(&self, table: usize, settings: &SeriesSettings) -> Option<Series> {
+        let mut series = Series::new(
+            format!("{} table {}", self.name, table + 1),
+            self.tables.get(table)?.players.clone(),
+            self.games,
+            Some(settings.clone()),
+        );
+        series.games = vec![self.game(table, 0)?];
+        Some(series)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tables.iter().all(|table| table.series.is_some())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub player: String,
+    /// Sum of the player's series results
+    pub score: i32,
+    pub series_played: u32,
+    pub series_won: u32,
+    /// Sum of the scores of all opponents met, the last tiebreak
+    pub buchholz: i32,
+}
+
+/**
+ * Several rounds of series with changing partnerships.
+ * Rounds are paired one after another, each once the previous one is reported complete.
+ * Standings are ordered by score, then series won, then Buchholz.
+ */
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub name: String,
+    pub players: Vec<String>,
+    pub settings: TournamentSettings,
+    pub rounds: Vec<Round>,
+}
+
+impl Tournament {
+    pub fn new(
+        name: String,
+        players: Vec<String>,
+        settings: TournamentSettings,
+    ) -> Result<Self, TournamentError> {
+        if players.is_empty() || !players.len().is_multiple_of(4) {
+            return Err(TournamentError::PlayerCount);
+        }
+        for (i, player) in players.iter().enumerate() {
+            if players[..i].contains(player) {
+                return Err(TournamentError::DuplicatePlayer);
+            }
+        }
+        Ok(Tournament {
+            name,
+            players,
+            settings,
+            rounds: vec![],
+        })
+    }
+
+    pub fn current_round(&self) -> Option<&Round> {
+        self.rounds.last()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.rounds.len() >= self.settings.rounds && self.rounds.iter().all(Round::is_finished)
+    }
+
+    /// Pairs and seats the next round.
+    pub fn next_round(&mut self) -> Result<&Round, TournamentError> {
+        if self
+            .current_round()
+            .is_some_and(|round| !round.is_finished())
+        {
+            return Err(TournamentError::RoundNotFinished);
+        }
+        if self.rounds.len() >= self.settings.rounds {
+            return Err(TournamentError::TournamentFinished);
+        }
+        let number = self.rounds.len();
+        let tables = match self.settings.pairing {
+            Pairing::RoundRobin => round_robin_tables(&self.players, number),
+            Pairing::Swiss => self.swiss_tables(),
+        };
+        self.rounds.push(Round {
+            name: format!("{} round {}", self.name, number + 1),
+            first_deal: self
+                .settings
+                .seed
+                .wrapping_add(number as u64 * self.settings.games_per_round as u64),
+            games: self.settings.games_per_round,
+            tables: tables
+                .into_iter()
+                .map(|players| TableAssignment {
+                    players,
+                    series: None,
+                })
+                .collect(),
+        });
+        Ok(self.rounds.last().unwrap())
+    }
+
+    /// Series for a table of the current round, see `Round::series`.
+    pub fn series(&self, table: usize) -> Result<Series, TournamentError> {
+        let round = self.current_round().ok_or(TournamentError::NoRound)?;
+        round
+            .series(table, &self.settings.series)
+            .ok_or(TournamentError::UnknownTable)
+    }
+
+    /// Reports the complete series of a table in the current round.
+    pub fn report(&mut self, table: usize, series: Series) -> Result<(), TournamentError> {
+        let round = self.rounds.last_mut().ok_or(TournamentError::NoRound)?;
+        let first_deal = round.first_deal;
+        let assignment = round
+            .tables
+            .get_mut(table)
+            .ok_or(TournamentError::UnknownTable)?;
+        if series.players_names != assignment.players {
+            return Err(TournamentError::WrongPlayers);
+        }
+        if !series.is_complete() {
+            return Err(TournamentError::SeriesNotComplete);
+        }
+        for (i, game) in series.games.iter().enumerate() {
+            if game.info.player_start_cards != seeded_cards(first_deal.wrapping_add(i as u64)) {
+                return Err(TournamentError::WrongDeal);
+            }
+        }
+        assignment.series = Some(series);
+        Ok(())
+    }
+
+    /// Standings over all reported series, best first.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .players
+            .iter()
+            .map(|player| Standing {
+                player: player.clone(),
+                score: 0,
+                series_played: 0,
+                series_won: 0,
+                buchholz: 0,
+            })
+            .collect();
+        let index: HashMap<&String, usize> = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p, i))
+            .collect();
+        let mut opponents: Vec<Vec<usize>> = vec![vec![]; self.players.len()];
+        for (players, series) in self.reported() {
+            let results = series.player_results();
+            for (seat, player) in players.iter().enumerate() {
+                let i = index[player];
+                standings[i].score += results[seat];
+                standings[i].series_played += 1;
+                standings[i].series_won += (results[seat] > 0) as u32;
+                opponents[i].extend([1, 3].map(|side| index[&players[(seat + side) % 4]]));
+            }
+        }
+        let scores: Vec<i32> = standings.iter().map(|standing| standing.score).collect();
+        for (standing, opponents) in standings.iter_mut().zip(opponents) {
+            standing.buchholz = opponents.iter().map(|i| scores[*i]).sum();
+        }
+        standings.sort_by_key(|standing| {
+            (
+                Reverse(standing.score),
+                Reverse(standing.series_won),
+                Reverse(standing.buchholz),
+                standing.player.clone(),
+            )
+        });
+        standings
+    }
+
+    fn reported(&self) -> impl Iterator<Item = (&[String; 4], &Series)> {
+        self.rounds.iter().flat_map(|round| {
+            round
+                .tables
+                .iter()
+                .filter_map(|table| Some((&table.players, table.series.as_ref()?)))
+        })
+    }
+
+    /// How often two players were partners so far.
+    fn partnered(&self, a: &str, b: &str) -> usize {
+        self.rounds
+            .iter()
+            .flat_map(|round| &round.tables)
+            .filter(|table| {
+                (0..4).any(|seat| table.players[seat] == a && table.players[(seat + 2) % 4] == b)
+            })
+            .count()
+    }
+
+    /**
+     * Tables of four neighbours in the standings, the first round is drawn from the seed.
+     * Within a table the partnering with the fewest repeated partners is chosen,
+     * preferring the strongest with the weakest player.
+     */
+    fn swiss_tables(&self) -> Vec<[String; 4]> {
+        let order: Vec<String> = match self.rounds.is_empty() {
+            true => {
+                let mut players = self.players.clone();
+                players.shuffle(&mut StdRng::seed_from_u64(self.settings.seed));
+                players
+            }
+            false => self
+                .standings()
+                .into_iter()
+                .map(|standing| standing.player)
+                .collect(),
+        };
+        order
+            .chunks(4)
+            .map(|group| {
+                let options = [[0, 1, 3, 2], [0, 1, 2, 3], [0, 2, 1, 3]];
+                let seats = options
+                    .into_iter()
+                    .min_by_key(|seats| {
+                        self.partnered(&group[seats[0]], &group[seats[2]])
+                            + self.partnered(&group[seats[1]], &group[seats[3]])
+                    })
+                    .unwrap();
+                seats.map(|i| group[i].clone())
+            })
+            .collect()
+    }
+}
+
+/// Partnerships by the circle method, two consecutive partnerships share a table.
+fn round_robin_tables(players: &[String], round: usize) -> Vec<[String; 4]> {
+    let n = players.len();
+    let rotating: Vec<usize> = (0..n - 1).map(|i| (i + round) % (n - 1)).collect();
+    let mut partnerships = vec![(n - 1, rotating[0])];
+    partnerships.extend((1..n / 2).map(|i| (rotating[i], rotating[n - 1 - i])));
+    partnerships
+        .chunks(2)
+        .map(|pair| [pair[0].0, pair[1].0, pair[0].1, pair[1].1].map(|i| players[i].clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::agent::{heuristic_agents, play_to_end};
+
+    fn helper_players(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("P{}", i)).collect()
+    }
+
+    #[test]
+    fn test_round_robin_partners_everyone_once() {
+        let settings = TournamentSettings {
+            pairing: Pairing::RoundRobin,
+            rounds: 7,
+            games_per_round: 1,
+            ..TournamentSettings::default()
+        };
+        let mut tournament =
+            Tournament::new(String::from("RR"), helper_players(8), settings).unwrap();
+        for _ in 0..7 {
+            tournament.next_round().unwrap();
+            for table in &mut tournament.rounds.last_mut().unwrap().tables {
+                table.series = Some(Series::new(String::new(), table.players.clone(), 0, None));
+            }
+        }
+        assert_eq!(
+            tournament.next_round().err(),
+            Some(TournamentError::TournamentFinished)
+        );
+        for a in &tournament.players {
+            for b in tournament.players.iter().filter(|b| *b != a) {
+                assert_eq!(tournament.partnered(a, b), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_swiss_tournament() {
+        assert_eq!(
+            Tournament::new(String::from("T"), helper_players(6), Default::default()).err(),
+            Some(TournamentError::PlayerCount)
+        );
+        let settings = TournamentSettings {
+            rounds: 2,
+            games_per_round: 2,
+            seed: 11,
+            ..TournamentSettings::default()
+        };
+        let mut tournament =
+            Tournament::new(String::from("Swiss"), helper_players(8), settings).unwrap();
+        for round in 0..2 {
+            tournament.next_round().unwrap();
+            assert_eq!(
+                tournament.next_round().err(),
+                Some(TournamentError::RoundNotFinished)
+            );
+            for table in 0..2 {
+                let mut series = tournament.series(table).unwrap();
+                assert_eq!(
+                    tournament.report(table, series.clone()),
+                    Err(TournamentError::SeriesNotComplete)
+                );
+                let mut agents = heuristic_agents(0);
+                for game in 0..2 {
+                    let played = play_to_end(series.games.pop().unwrap(), &mut agents);
+                    series.games.push(played);
+                    if let Some(next) = tournament.current_round().unwrap().game(table, game + 1) {
+                        series.games.push(next);
+                    }
+                }
+                tournament.report(table, series).unwrap();
+            }
+            // duplicate deals: both tables played the same cards
+            let tables = &tournament.rounds[round].tables;
+            let deals = tables.iter().map(|table| {
+                table.series.as_ref().unwrap().games[1]
+                    .info
+                    .player_start_cards
+                    .clone()
+            });
+            assert!(deals
+                .clone()
+                .all(|deal| deal == seeded_cards(11 + 2 * round as u64 + 1)));
+        }
+        assert!(tournament.is_finished());
+
+        let standings = tournament.standings();
+        assert_eq!(standings.len(), 8);
+        assert_eq!(standings.iter().map(|s| s.score).sum::<i32>(), 0);
+        assert!(standings.iter().all(|s| s.series_played == 2));
+        assert!(standings.windows(2).all(|w| w[0].score >= w[1].score));
+        for a in &tournament.players {
+            for b in tournament.players.iter().filter(|b| *b != a) {
+                assert!(tournament.partnered(a, b) <= 1);
+            }
+        }
+    }
+}
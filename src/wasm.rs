@@ -0,0 +1,169 @@
+use wasm_bindgen::prelude::*;
+
+use crate::game::cards::{allowed_cards, Card};
+use crate::game::gameevent::GameAction;
+use crate::game::gameinfo::GameInfoPlayer;
+use crate::game::notation::parse_suit_str;
+use crate::game::player::PlaceAtTable;
+use crate::game::Game;
+
+/*
+JavaScript bindings, built with wasm-bindgen.
+There is no clock or thread rng on wasm32: event times stay empty and games are dealt from
+a seed or given cards. For a random deal pass a seed from `crypto.getRandomValues`.
+Actions and cards are strings in notation, e.g. `2 play r-A`.
+ */
+
+fn js_error(e: impl std::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+fn players(names: Vec<String>) -> Result<[String; 4], JsError> {
+    names
+        .try_into()
+        .map_err(|_| JsError::new("a game needs four players"))
+}
+
+fn seat(seat: u8) -> Result<PlaceAtTable, JsError> {
+    match seat {
+        0..=3 => Ok(PlaceAtTable(seat)),
+        _ => Err(JsError::new(&format!("{} is not a seat", seat))),
+    }
+}
+
+fn parse_cards(cards: &[String]) -> Result<Vec<Card>, JsError> {
+    cards
+        .iter()
+        .map(|card| card.parse().map_err(js_error))
+        .collect()
+}
+
+fn notation(actions: impl Iterator<Item = GameAction>) -> Vec<String> {
+    actions.map(|action| action.to_string()).collect()
+}
+
+#[wasm_bindgen(js_name = Game)]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen(js_class = Game)]
+impl WasmGame {
+    /// Deals the same cards as `Game::new_seeded` on the server, random seeds come from JS.
+    #[wasm_bindgen(constructor)]
+    pub fn new(name: String, players: Vec<String>, seed: u64) -> Result<WasmGame, JsError> {
+        Ok(WasmGame {
+            game: Game::new_seeded(name, self::players(players)?, seed),
+        })
+    }
+
+    /// A game with known hands, 36 cards of which seat `i` gets cards `9i` to `9i + 8`.
+    #[wasm_bindgen(js_name = withCards)]
+    pub fn with_cards(
+        name: String,
+        players: Vec<String>,
+        cards: Vec<String>,
+    ) -> Result<WasmGame, JsError> {
+        let cards = parse_cards(&cards)?;
+        if cards.len() != 36 {
+            return Err(JsError::new("a deal has 36 cards"));
+        }
+        let hands = [0, 1, 2, 3].map(|seat| cards[9 * seat..9 * seat + 9].to_vec());
+        Ok(WasmGame {
+            game: Game::new(name, self::players(players)?, Some(hands)),
+        })
+    }
+
+    #[wasm_bindgen(js_name = legalActions)]
+    pub fn legal_actions(&self) -> Vec<String> {
+        notation(self.game.legal.iter())
+    }
+
+    #[wasm_bindgen(js_name = legalActionsFor)]
+    pub fn legal_actions_for(&self, seat: u8) -> Result<Vec<String>, JsError> {
+        let seat = self::seat(seat)?;
+        Ok(notation(
+            self.game
+                .legal
+                .iter()
+                .filter(|action| action.player == seat),
+        ))
+    }
+
+    #[wasm_bindgen(js_name = isLegal)]
+    pub fn is_legal(&self, action: &str) -> bool {
+        self.game.action_from_notation(action).is_ok()
+    }
+
+    /// Applies one action, with or without a seat, the game itself is unchanged.
+    #[wasm_bindgen(js_name = applyAction)]
+    pub fn apply_action(&self, action: &str) -> Result<WasmGame, JsError> {
+        let action = self.game.action_from_notation(action).map_err(js_error)?;
+        let game = self
+            .game
+            .apply_action(action)
+            .map_err(|e| JsError::new(&format!("{:?}", e)))?;
+        Ok(WasmGame { game })
+    }
+
+    /// Applies a `;` separated move list.
+    #[wasm_bindgen(js_name = applyNotation)]
+    pub fn apply_notation(&self, moves: &str) -> Result<WasmGame, JsError> {
+        let game = self.game.apply_notation(moves).map_err(js_error)?;
+        Ok(WasmGame { game })
+    }
+
+    /// What the seat sees as a plain object, see `GameInfoPlayer::to_json`.
+    pub fn view(&self, seat: u8) -> Result<JsValue, JsError> {
+        let info = GameInfoPlayer::from_game(self.game.clone(), self::seat(seat)?);
+        js_sys::JSON::parse(&info.to_json().to_string())
+            .map_err(|_| JsError::new("the view is no valid JSON"))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn phase(&self) -> String {
+        format!("{:?}", self.game.state.phase)
+    }
+
+    #[wasm_bindgen(getter, js_name = playerAtTurn)]
+    pub fn player_at_turn(&self) -> u8 {
+        self.game.state.player_at_turn.0
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn value(&self) -> i32 {
+        self.game.state.value.0
+    }
+
+    pub fn ended(&self) -> bool {
+        self.game.ended()
+    }
+}
+
+/**
+ * Cards of the hand which may be played into the trick, needs only what the player sees.
+ * `trump` is a suit like `r`, `firstTrick` is true during the first trick of the game.
+ */
+#[wasm_bindgen(js_name = playableCards)]
+pub fn playable_cards(
+    hand: Vec<String>,
+    trick: Vec<String>,
+    trump: Option<String>,
+    first_trick: bool,
+) -> Result<Vec<String>, JsError> {
+    let hand = parse_cards(&hand)?;
+    let trick = parse_cards(&trick)?;
+    let trump = trump
+        .map(|suit| parse_suit_str(&suit))
+        .transpose()
+        .map_err(js_error)?;
+    Ok(allowed_cards(
+        trick.iter().collect(),
+        hand.iter().collect(),
+        trump,
+        first_trick,
+    )
+    .into_iter()
+    .map(|card| card.to_string())
+    .collect())
+}